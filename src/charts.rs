@@ -0,0 +1,148 @@
+use crate::disk_benchmark::LatencyHistogram;
+
+/// A range of latencies (in seconds) and the number of operations that fell within it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: u64,
+}
+
+/// Regroups the histogram into at most `bins` logarithmically sized bins spanning the fastest to the slowest
+/// operation.
+pub fn latency_bins(histogram: &LatencyHistogram, bins: usize) -> Vec<HistogramBin> {
+    if histogram.is_empty() {
+        return Vec::new();
+    }
+    let (min, max) = (histogram.percentile(0.0), histogram.percentile(1.0));
+    let bins = if max > min { bins.max(1) } else { 1 };
+    let ratio = (max / min).powf(1.0 / bins as f64);
+    let mut result: Vec<HistogramBin> = (0..bins)
+        .map(|i| HistogramBin {
+            lower: min * ratio.powi(i as i32),
+            upper: min * ratio.powi(i as i32 + 1),
+            count: 0,
+        })
+        .collect();
+    for (latency, count) in histogram.buckets() {
+        let index = if bins > 1 {
+            ((latency / min).ln() / ratio.ln()) as usize
+        } else {
+            0
+        };
+        result[index.min(bins - 1)].count += count;
+    }
+    result
+}
+
+/// The percentage of operations at or below each of `points` latencies, spaced logarithmically from the fastest to
+/// the slowest operation. Returns the latencies and the percentages.
+pub fn latency_cdf(histogram: &LatencyHistogram, points: usize) -> (Vec<f64>, Vec<f64>) {
+    if histogram.is_empty() || points == 0 {
+        return (Vec::new(), Vec::new());
+    }
+    let (min, max) = (histogram.percentile(0.0), histogram.percentile(1.0));
+    let buckets: Vec<(f64, u64)> = histogram.buckets().collect();
+    let total: u64 = buckets.iter().map(|(_, count)| count).sum();
+    let step = (max / min).powf(1.0 / (points.max(2) - 1) as f64);
+    let latencies: Vec<f64> = (0..points).map(|i| min * step.powi(i as i32)).collect();
+    let percentages = latencies
+        .iter()
+        .enumerate()
+        .map(|(i, latency)| {
+            // The last point is the slowest operation, don't let rounding leave it short of 100%.
+            if i == points - 1 {
+                return 100.0;
+            }
+            let below: u64 = buckets
+                .iter()
+                .take_while(|(value, _)| value <= latency)
+                .map(|(_, count)| count)
+                .sum();
+            below as f64 / total as f64 * 100.0
+        })
+        .collect();
+    (latencies, percentages)
+}
+
+/// A horizontal bar `width` characters long at `max`, using eighth blocks for the remainder.
+pub fn bar(value: u64, max: u64, width: usize) -> String {
+    const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    if max == 0 {
+        return String::new();
+    }
+    let eighths = (value as f64 / max as f64 * width as f64 * 8.0).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    let remainder = eighths % 8;
+    if remainder > 0 {
+        bar.push(EIGHTHS[remainder]);
+    }
+    bar
+}
+
+/// A one line chart of `values` scaled to the largest value.
+pub fn sparkline(values: &[f64]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    values
+        .iter()
+        .map(|value| {
+            if max > 0.0 {
+                LEVELS[((value / max) * 7.0).round() as usize]
+            } else {
+                LEVELS[0]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram() -> LatencyHistogram {
+        let mut histogram = LatencyHistogram::default();
+        // Bimodal: fast cache hits and slow misses.
+        for _ in 0..900 {
+            histogram.record(10e-6);
+        }
+        for _ in 0..100 {
+            histogram.record(1e-3);
+        }
+        histogram
+    }
+
+    #[test]
+    fn test_latency_bins() {
+        let bins = latency_bins(&histogram(), 10);
+        assert_eq!(bins.len(), 10);
+        assert_eq!(bins.first().unwrap().count, 900);
+        assert_eq!(bins.last().unwrap().count, 100);
+        assert_eq!(bins.iter().map(|b| b.count).sum::<u64>(), 1000);
+        assert!(bins.windows(2).all(|w| w[0].upper == w[1].lower));
+        assert!(latency_bins(&LatencyHistogram::default(), 10).is_empty());
+    }
+
+    #[test]
+    fn test_latency_cdf() {
+        let (latencies, percentages) = latency_cdf(&histogram(), 20);
+        assert_eq!(latencies.len(), 20);
+        assert_eq!(percentages[0], 90.0);
+        assert_eq!(percentages[18], 90.0);
+        assert_eq!(percentages[19], 100.0);
+        assert!(percentages.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(10, 10, 4), "████");
+        assert_eq!(bar(5, 10, 3), "█▌");
+        assert_eq!(bar(0, 10, 4), "");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0.0, 1.0, 2.0]), "▁▅█");
+        assert_eq!(sparkline(&[0.0, 0.0]), "▁▁");
+    }
+}
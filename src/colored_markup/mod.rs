@@ -158,7 +158,7 @@ fn test_stylesheet() {
 }
 
 impl StyleSheet<'_> {
-    fn parse_template(t: &str) -> Vec<Part> {
+    fn parse_template(t: &str) -> Vec<Part<'_>> {
         lazy_static! {
             static ref REGEX: Regex = Regex::new(
                 r"(?x)
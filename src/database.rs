@@ -0,0 +1,291 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::disk_benchmark::*;
+
+/// Version of the schema below, stored in the database's `user_version`.
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE sessions (
+    id INTEGER PRIMARY KEY,
+    created REAL NOT NULL,
+    host TEXT,
+    path TEXT NOT NULL,
+    args TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    block_size INTEGER NOT NULL,
+    options TEXT NOT NULL,
+    volume TEXT
+);
+CREATE INDEX sessions_created ON sessions (created);
+
+CREATE TABLE runs (
+    id INTEGER PRIMARY KEY,
+    session_id INTEGER NOT NULL REFERENCES sessions (id) ON DELETE CASCADE,
+    mode TEXT NOT NULL,
+    cache TEXT NOT NULL,
+    cycles INTEGER NOT NULL,
+    mean REAL NOT NULL,
+    median REAL NOT NULL,
+    standard_deviation REAL NOT NULL,
+    min REAL NOT NULL,
+    max REAL NOT NULL,
+    total_throughput REAL NOT NULL,
+    iops REAL NOT NULL,
+    latency_p50 REAL,
+    latency_p99 REAL,
+    statistics TEXT NOT NULL
+);
+CREATE INDEX runs_session_id ON runs (session_id);
+
+CREATE TABLE cycles (
+    run_id INTEGER NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    cycle INTEGER NOT NULL,
+    bytes INTEGER NOT NULL,
+    ops INTEGER NOT NULL,
+    elapsed REAL NOT NULL,
+    PRIMARY KEY (run_id, cycle)
+);
+";
+
+/// An SQLite database of sessions, their runs and the runs' cycles.
+///
+/// The columns hold what's needed to query the history, the complete options and statistics are kept as JSON.
+pub struct Database {
+    connection: Connection,
+}
+
+/// Which runs to return from [`Database::history`]. Every criterion that is set must match.
+#[derive(Debug, Default)]
+pub struct HistoryFilter {
+    pub host: Option<String>,
+    /// Matches the test file's path, `%` and `_` are wildcards.
+    pub path: Option<String>,
+    pub mode: Option<ReadWrite>,
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+}
+
+/// A run from the history, with the session it belongs to.
+#[derive(Debug)]
+pub struct HistoryRow {
+    pub session_id: i64,
+    pub created: SystemTime,
+    pub host: Option<String>,
+    pub path: String,
+    pub mode: String,
+    pub cache: String,
+    pub block_size: usize,
+    pub cycles: usize,
+    pub mean: f64,
+    pub standard_deviation: f64,
+    pub total_throughput: f64,
+    pub iops: f64,
+    pub latency_p99: Option<f64>,
+}
+
+impl Database {
+    /// Opens the database at `path`, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        let version: i32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        match version {
+            0 => {
+                connection.execute_batch(SCHEMA)?;
+                connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            }
+            SCHEMA_VERSION => {}
+            _ => anyhow::bail!(
+                "Database {} has an unsupported schema version ({}).",
+                path.display(),
+                version
+            ),
+        }
+        Ok(Database { connection })
+    }
+
+    /// Stores the session and all its runs and cycles, returns the session's id.
+    pub fn insert(&mut self, session: &SessionResult) -> Result<i64> {
+        let transaction = self.connection.transaction()?;
+        let options = &session.options;
+        transaction.execute(
+            "INSERT INTO sessions (created, host, path, args, file_size, block_size, options, volume)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                timestamp(session.created),
+                session.host,
+                options.path.display().to_string(),
+                session.args,
+                options.file_size,
+                options.block_size,
+                serde_json::to_string(options)?,
+                session
+                    .volume
+                    .as_ref()
+                    .map(serde_json::to_string)
+                    .transpose()?,
+            ],
+        )?;
+        let session_id = transaction.last_insert_rowid();
+
+        for run in session.runs.iter() {
+            let statistics = &run.statistics;
+            transaction.execute(
+                "INSERT INTO runs (session_id, mode, cache, cycles, mean, median, standard_deviation, min, max,
+                                   total_throughput, iops, latency_p50, latency_p99, statistics)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                params![
+                    session_id,
+                    run.mode.to_string(),
+                    run.cache.to_string(),
+                    run.cycle_results.len(),
+                    statistics.mean,
+                    statistics.median,
+                    statistics.standard_deviation,
+                    statistics.min,
+                    statistics.max,
+                    statistics.total_throughput,
+                    statistics.iops,
+                    statistics.latency.as_ref().map(|l| l.p50),
+                    statistics.latency.as_ref().map(|l| l.p99),
+                    serde_json::to_string(statistics)?,
+                ],
+            )?;
+            let run_id = transaction.last_insert_rowid();
+
+            let mut statement = transaction.prepare(
+                "INSERT INTO cycles (run_id, cycle, bytes, ops, elapsed) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for cycle in run.cycle_results.iter() {
+                statement.execute(params![
+                    run_id,
+                    cycle.cycle,
+                    cycle.bytes,
+                    cycle.ops,
+                    cycle.elapsed
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(session_id)
+    }
+
+    /// The runs matching `filter`, oldest first.
+    pub fn history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryRow>> {
+        let mut statement = self.connection.prepare(
+            "SELECT sessions.id, sessions.created, sessions.host, sessions.path, runs.mode, runs.cache,
+                    sessions.block_size, runs.cycles, runs.mean, runs.standard_deviation, runs.total_throughput,
+                    runs.iops, runs.latency_p99
+             FROM runs JOIN sessions ON runs.session_id = sessions.id
+             WHERE (?1 IS NULL OR sessions.host = ?1)
+               AND (?2 IS NULL OR sessions.path LIKE ?2)
+               AND (?3 IS NULL OR runs.mode = ?3)
+               AND (?4 IS NULL OR sessions.created >= ?4)
+               AND (?5 IS NULL OR sessions.created < ?5)
+             ORDER BY sessions.created, sessions.id, runs.id",
+        )?;
+        let rows = statement.query_map(
+            params![
+                filter.host,
+                filter.path,
+                filter.mode.as_ref().map(|m| m.to_string()),
+                filter.since.map(timestamp),
+                filter.until.map(timestamp),
+            ],
+            |row| {
+                Ok(HistoryRow {
+                    session_id: row.get(0)?,
+                    created: UNIX_EPOCH + Duration::from_secs_f64(row.get(1)?),
+                    host: row.get(2)?,
+                    path: row.get(3)?,
+                    mode: row.get(4)?,
+                    cache: row.get(5)?,
+                    block_size: row.get(6)?,
+                    cycles: row.get(7)?,
+                    mean: row.get(8)?,
+                    standard_deviation: row.get(9)?,
+                    total_throughput: row.get(10)?,
+                    iops: row.get(11)?,
+                    latency_p99: row.get(12)?,
+                })
+            },
+        )?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+/// Seconds since the Unix epoch.
+fn timestamp(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_database() {
+        let tmp_dir = tempdir().unwrap();
+        let mut options = SessionOptions::new(&tmp_dir.path().join("test.dat"), 64 * 1024, 4096, 2);
+        options.no_progress = true;
+        let session = Session::new(options).main().unwrap();
+
+        let path = tmp_dir.path().join("results.db");
+        let mut database = Database::open(&path).unwrap();
+        let first = database.insert(&session).unwrap();
+        drop(database);
+        // Reopening doesn't recreate the schema.
+        let mut database = Database::open(&path).unwrap();
+        let second = database.insert(&session).unwrap();
+        assert_ne!(first, second);
+
+        let cycles: usize = database
+            .connection
+            .query_row("SELECT COUNT(*) FROM cycles", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(cycles, 8);
+
+        let all = database.history(&HistoryFilter::default()).unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].session_id, first);
+        assert_eq!(all[0].mode, "Read");
+        assert_eq!(
+            all[0].total_throughput,
+            session.runs[0].statistics.total_throughput
+        );
+
+        let writes = database
+            .history(&HistoryFilter {
+                mode: Some(ReadWrite::Write),
+                path: Some("%test.dat".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(writes.len(), 2);
+        assert!(writes.iter().all(|r| r.mode == "Write"));
+
+        let none = database
+            .history(&HistoryFilter {
+                host: Some("no such host".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(none.is_empty());
+
+        let later = database
+            .history(&HistoryFilter {
+                since: Some(session.created + Duration::from_secs(1)),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(later.is_empty());
+    }
+}
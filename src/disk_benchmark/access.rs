@@ -0,0 +1,389 @@
+use anyhow::{anyhow, ensure, Result};
+use rand::{rngs::ThreadRng, Rng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::support::*;
+
+/// The order in which a non-random cycle visits the blocks of the test file.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, JsonSchema, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AccessPattern {
+    /// Forward from the start of the file.
+    #[default]
+    Sequential,
+    /// Backward from the end of the file.
+    Reverse,
+    /// Forward, `stride` bytes between the start of each operation. Each pass over the file starts one block later
+    /// than the previous one so every block is eventually visited.
+    Stride { stride: usize },
+    /// The file is split into `streams` equal regions which are read or written sequentially in round robin.
+    Interleaved { streams: usize },
+}
+
+impl Display for AccessPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessPattern::Sequential => write!(f, "sequential"),
+            AccessPattern::Reverse => write!(f, "reverse"),
+            AccessPattern::Stride { stride } => {
+                write!(f, "stride:{}", DataSize::from(*stride).to_human_string())
+            }
+            AccessPattern::Interleaved { streams } => write!(f, "interleaved:{}", streams),
+        }
+    }
+}
+
+pub fn parse_access_pattern(s: &str) -> Result<AccessPattern> {
+    let pattern = match s.split_once(':').unwrap_or((s, "")) {
+        ("sequential", "") => AccessPattern::Sequential,
+        ("reverse", "") => AccessPattern::Reverse,
+        ("stride", stride) => AccessPattern::Stride {
+            stride: parse_data_size(stride)?.into(),
+        },
+        ("interleaved", streams) => AccessPattern::Interleaved {
+            streams: streams.parse()?,
+        },
+        _ => return Err(anyhow!("Invalid access pattern")),
+    };
+    match pattern {
+        AccessPattern::Stride { stride } => {
+            ensure!(stride > 0, "Stride must be greater than zero.")
+        }
+        AccessPattern::Interleaved { streams } => {
+            ensure!(streams > 0, "Number of streams must be greater than zero.")
+        }
+        _ => {}
+    }
+    Ok(pattern)
+}
+
+/// How random offsets are distributed over the blocks of the test file.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, JsonSchema, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OffsetDistribution {
+    /// Every block is equally likely.
+    #[default]
+    Uniform,
+    /// Zipfian with skew `theta` (0 < theta < 1), the first blocks are the hottest.
+    Zipf { theta: f64 },
+    /// Self-similar "80/20" distribution: a fraction `1 - h` of accesses go to the first fraction `h` of blocks.
+    Pareto { h: f64 },
+    /// Normal distribution around a hot region. `mean` and `stddev` are fractions of the file size.
+    Normal { mean: f64, stddev: f64 },
+}
+
+impl Display for OffsetDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OffsetDistribution::Uniform => write!(f, "uniform"),
+            OffsetDistribution::Zipf { theta } => write!(f, "zipf:{}", theta),
+            OffsetDistribution::Pareto { h } => write!(f, "pareto:{}", h),
+            OffsetDistribution::Normal { mean, stddev } => write!(f, "normal:{},{}", mean, stddev),
+        }
+    }
+}
+
+pub fn parse_offset_distribution(s: &str) -> Result<OffsetDistribution> {
+    let (name, params) = s.split_once(':').unwrap_or((s, ""));
+    let params = params
+        .split(',')
+        .filter(|p| !p.is_empty())
+        .map(|p| p.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()?;
+    let distribution = match (name, params.as_slice()) {
+        ("uniform", []) => OffsetDistribution::Uniform,
+        ("zipf", []) => OffsetDistribution::Zipf { theta: 0.99 },
+        ("zipf", [theta]) => OffsetDistribution::Zipf { theta: *theta },
+        ("pareto", []) => OffsetDistribution::Pareto { h: 0.2 },
+        ("pareto", [h]) => OffsetDistribution::Pareto { h: *h },
+        ("normal", [mean, stddev]) => OffsetDistribution::Normal {
+            mean: *mean,
+            stddev: *stddev,
+        },
+        _ => return Err(anyhow!("Invalid offset distribution")),
+    };
+    distribution.validate()?;
+    Ok(distribution)
+}
+
+impl OffsetDistribution {
+    fn validate(&self) -> Result<()> {
+        match *self {
+            OffsetDistribution::Uniform => {}
+            OffsetDistribution::Zipf { theta } => {
+                ensure!(
+                    theta > 0.0 && theta < 1.0,
+                    "Zipf theta must be between 0 and 1."
+                )
+            }
+            OffsetDistribution::Pareto { h } => {
+                ensure!(h > 0.0 && h < 1.0, "Pareto h must be between 0 and 1.")
+            }
+            OffsetDistribution::Normal { mean, stddev } => {
+                ensure!(
+                    (0.0..=1.0).contains(&mean),
+                    "Normal mean must be between 0 and 1."
+                );
+                ensure!(stddev > 0.0, "Normal stddev must be greater than zero.");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An `OffsetDistribution` with its constants precomputed for a fixed number of slots.
+#[derive(Debug)]
+enum Sampler {
+    Uniform,
+    // From "Quickly Generating Billion-Record Synthetic Databases", Gray et al.
+    Zipf {
+        theta: f64,
+        alpha: f64,
+        zetan: f64,
+        eta: f64,
+    },
+    Pareto {
+        exponent: f64,
+    },
+    Normal {
+        mean: f64,
+        stddev: f64,
+    },
+}
+
+impl Sampler {
+    fn new(distribution: &OffsetDistribution, slots: usize) -> Self {
+        match *distribution {
+            OffsetDistribution::Uniform => Sampler::Uniform,
+            OffsetDistribution::Zipf { theta } => {
+                let zeta = |n: usize| (1..=n).map(|i| 1.0 / (i as f64).powf(theta)).sum::<f64>();
+                let zetan = zeta(slots);
+                let zeta2 = zeta(2);
+                let eta = (1.0 - (2.0 / slots as f64).powf(1.0 - theta)) / (1.0 - zeta2 / zetan);
+                Sampler::Zipf {
+                    theta,
+                    alpha: 1.0 / (1.0 - theta),
+                    zetan,
+                    eta,
+                }
+            }
+            OffsetDistribution::Pareto { h } => Sampler::Pareto {
+                exponent: h.ln() / (1.0 - h).ln(),
+            },
+            OffsetDistribution::Normal { mean, stddev } => Sampler::Normal { mean, stddev },
+        }
+    }
+
+    /// Returns a slot index in `0..slots`.
+    fn sample(&self, rng: &mut ThreadRng, slots: usize) -> usize {
+        let slot = match *self {
+            Sampler::Uniform => rng.random_range(0..slots),
+            Sampler::Zipf {
+                theta,
+                alpha,
+                zetan,
+                eta,
+            } => {
+                let u: f64 = rng.random();
+                let uz = u * zetan;
+                if uz < 1.0 {
+                    0
+                } else if uz < 1.0 + 0.5_f64.powf(theta) {
+                    1
+                } else {
+                    (slots as f64 * (eta * u - eta + 1.0).powf(alpha)) as usize
+                }
+            }
+            Sampler::Pareto { exponent } => {
+                let u: f64 = rng.random();
+                (slots as f64 * u.powf(exponent)) as usize
+            }
+            Sampler::Normal { mean, stddev } => loop {
+                // Box-Muller, resampling anything that falls outside of the file.
+                let u1: f64 = 1.0 - rng.random::<f64>();
+                let u2: f64 = rng.random();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                let x = mean + stddev * z;
+                if (0.0..1.0).contains(&x) {
+                    break (x * slots as f64) as usize;
+                }
+            },
+        };
+        slot.min(slots - 1)
+    }
+}
+
+/// Generates the file offset of each operation in a cycle.
+#[derive(Debug)]
+pub struct Offsets {
+    file_size: usize,
+    alignment: usize,
+    slots: usize,
+    pattern: AccessPattern,
+    random: Option<Sampler>,
+    position: usize,
+    pass: usize,
+    cursors: Vec<usize>,
+    operation: usize,
+    rng: ThreadRng,
+}
+
+impl Offsets {
+    /// `random` selects random offsets (aligned to `alignment`) instead of following `pattern`. `max_size` is the
+    /// largest operation that will be requested.
+    pub fn new(
+        file_size: usize,
+        alignment: usize,
+        max_size: usize,
+        pattern: &AccessPattern,
+        random: Option<&OffsetDistribution>,
+    ) -> Self {
+        let slots = (file_size - max_size) / alignment + 1;
+        let streams = match pattern {
+            AccessPattern::Interleaved { streams } => *streams,
+            _ => 0,
+        };
+        Offsets {
+            file_size,
+            alignment,
+            slots,
+            pattern: *pattern,
+            random: random.map(|d| Sampler::new(d, slots)),
+            position: 0,
+            pass: 0,
+            cursors: vec![0; streams],
+            operation: 0,
+            rng: rand::rng(),
+        }
+    }
+
+    /// Returns the offset for the next operation of `size` bytes.
+    pub fn next(&mut self, size: usize) -> usize {
+        if let Some(sampler) = &self.random {
+            return sampler.sample(&mut self.rng, self.slots) * self.alignment;
+        }
+        let offset = match self.pattern {
+            AccessPattern::Sequential => {
+                if self.position + size > self.file_size {
+                    self.position = 0;
+                }
+                let offset = self.position;
+                self.position += size;
+                offset
+            }
+            AccessPattern::Reverse => {
+                if self.position < size {
+                    self.position = self.file_size / self.alignment * self.alignment;
+                }
+                self.position -= size;
+                self.position
+            }
+            AccessPattern::Stride { stride } => {
+                if self.position + size > self.file_size {
+                    self.pass = (self.pass + self.alignment) % stride.max(self.alignment);
+                    self.position = self.pass;
+                }
+                let offset = self.position;
+                self.position += stride;
+                offset
+            }
+            AccessPattern::Interleaved { streams } => {
+                let region = self.file_size / streams / self.alignment * self.alignment;
+                let stream = self.operation % streams;
+                let cursor = &mut self.cursors[stream];
+                if *cursor + size > region {
+                    *cursor = 0;
+                }
+                let offset = stream * region + *cursor;
+                *cursor += size;
+                offset
+            }
+        };
+        self.operation += 1;
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hot_fraction(distribution: &OffsetDistribution, hot: std::ops::Range<usize>) -> f64 {
+        let mut offsets = Offsets::new(1000, 1, 1, &AccessPattern::Sequential, Some(distribution));
+        let samples = 10000;
+        let hits = (0..samples)
+            .map(|_| offsets.next(1))
+            .inspect(|offset| assert!(*offset < 1000))
+            .filter(|offset| hot.contains(offset))
+            .count();
+        hits as f64 / samples as f64
+    }
+
+    #[test]
+    fn test_parse_offset_distribution() {
+        assert_eq!(
+            parse_offset_distribution("uniform").unwrap(),
+            OffsetDistribution::Uniform
+        );
+        assert_eq!(
+            parse_offset_distribution("zipf:0.8").unwrap(),
+            OffsetDistribution::Zipf { theta: 0.8 }
+        );
+        assert_eq!(
+            parse_offset_distribution("pareto").unwrap(),
+            OffsetDistribution::Pareto { h: 0.2 }
+        );
+        assert_eq!(
+            parse_offset_distribution("normal:0.5,0.1").unwrap(),
+            OffsetDistribution::Normal {
+                mean: 0.5,
+                stddev: 0.1
+            }
+        );
+        assert!(parse_offset_distribution("zipf:1.5").is_err());
+        assert!(parse_offset_distribution("normal:0.5").is_err());
+        assert!(parse_offset_distribution("gaussian").is_err());
+    }
+
+    #[test]
+    fn test_distributions() {
+        assert!(hot_fraction(&OffsetDistribution::Uniform, 0..200) < 0.3);
+        assert!(hot_fraction(&OffsetDistribution::Zipf { theta: 0.99 }, 0..200) > 0.6);
+        assert!(hot_fraction(&OffsetDistribution::Pareto { h: 0.2 }, 0..200) > 0.7);
+        let normal = OffsetDistribution::Normal {
+            mean: 0.5,
+            stddev: 0.05,
+        };
+        assert!(hot_fraction(&normal, 400..600) > 0.9);
+    }
+
+    fn pattern_offsets(pattern: &str, count: usize) -> Vec<usize> {
+        let pattern = parse_access_pattern(pattern).unwrap();
+        let mut offsets = Offsets::new(8, 1, 1, &pattern, None);
+        (0..count).map(|_| offsets.next(1)).collect()
+    }
+
+    #[test]
+    fn test_access_patterns() {
+        assert_eq!(
+            pattern_offsets("sequential", 9),
+            vec![0, 1, 2, 3, 4, 5, 6, 7, 0]
+        );
+        assert_eq!(
+            pattern_offsets("reverse", 9),
+            vec![7, 6, 5, 4, 3, 2, 1, 0, 7]
+        );
+        assert_eq!(
+            pattern_offsets("stride:3B", 9),
+            vec![0, 3, 6, 1, 4, 7, 2, 5, 0]
+        );
+        assert_eq!(
+            pattern_offsets("interleaved:2", 9),
+            vec![0, 4, 1, 5, 2, 6, 3, 7, 0]
+        );
+        assert!(parse_access_pattern("stride:0B").is_err());
+        assert!(parse_access_pattern("interleaved:x").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::{anyhow, ensure, Result};
+use rand::{distr::weighted::WeightedIndex, distr::Distribution, rngs::ThreadRng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::support::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, JsonSchema, Serialize)]
+pub struct WeightedBlockSize {
+    pub size: usize,
+    pub weight: u32,
+}
+
+/// A distribution of block sizes, each operation of a cycle picks its size from it.
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
+pub struct BlockSizes {
+    pub sizes: Vec<WeightedBlockSize>,
+}
+
+impl BlockSizes {
+    /// The smallest size that can be picked, sizes without weight never are.
+    pub fn min(&self) -> usize {
+        self.picked().min().unwrap_or(0)
+    }
+
+    /// The largest size that can be picked.
+    pub fn max(&self) -> usize {
+        self.picked().max().unwrap_or(0)
+    }
+
+    /// Whether all sizes that can be picked are multiples of the smallest one, so offsets made up of them stay
+    /// aligned to it.
+    pub fn is_aligned(&self) -> bool {
+        let min = self.min();
+        min > 0 && self.picked().all(|size| size.is_multiple_of(min))
+    }
+
+    fn picked(&self) -> impl Iterator<Item = usize> + '_ {
+        self.sizes.iter().filter(|s| s.weight > 0).map(|s| s.size)
+    }
+}
+
+impl Display for BlockSizes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sizes: Vec<String> = self
+            .sizes
+            .iter()
+            .map(|s| format!("{}:{}", DataSize::from(s.size).to_human_string(), s.weight))
+            .collect();
+        write!(f, "{}", sizes.join(", "))
+    }
+}
+
+/// Parses either a weighted list (`4KB:50,64KB:30,1MB:20`) or a range (`4KB-1MB`). A range picks each power of two
+/// multiple of the lower bound up to the upper bound with equal probability.
+pub fn parse_block_sizes(s: &str) -> Result<BlockSizes> {
+    let sizes = if let Some((min, max)) = s.split_once('-') {
+        let min: usize = parse_data_size(min.trim())?.into();
+        let max: usize = parse_data_size(max.trim())?.into();
+        ensure!(min > 0 && min <= max, "Invalid block size range");
+        std::iter::successors(Some(min), |size| size.checked_mul(2))
+            .take_while(|size| *size <= max)
+            .map(|size| WeightedBlockSize { size, weight: 1 })
+            .collect()
+    } else {
+        s.split(',')
+            .map(|part| {
+                let (size, weight) = part.split_once(':').unwrap_or((part, "1"));
+                Ok(WeightedBlockSize {
+                    size: parse_data_size(size.trim())?.into(),
+                    weight: weight.trim().parse()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+    ensure!(
+        sizes.iter().all(|s| s.size > 0),
+        "Block sizes must be greater than zero."
+    );
+    ensure!(
+        sizes.iter().any(|s| s.weight > 0),
+        "At least one block size must have a weight greater than zero."
+    );
+    Ok(BlockSizes { sizes })
+}
+
+/// Picks the size of each operation, either fixed or from a `BlockSizes` distribution.
+#[derive(Debug)]
+pub struct BlockSizePicker {
+    block_size: usize,
+    weighted: Option<(Vec<usize>, WeightedIndex<u32>)>,
+    rng: ThreadRng,
+}
+
+impl BlockSizePicker {
+    pub fn new(block_size: usize, block_sizes: Option<&BlockSizes>) -> Result<Self> {
+        let weighted = match block_sizes {
+            Some(block_sizes) => {
+                let sizes = block_sizes.sizes.iter().map(|s| s.size).collect();
+                let index = WeightedIndex::new(block_sizes.sizes.iter().map(|s| s.weight))
+                    .map_err(|e| anyhow!("Invalid block size weights: {}", e))?;
+                Some((sizes, index))
+            }
+            None => None,
+        };
+        Ok(BlockSizePicker {
+            block_size,
+            weighted,
+            rng: rand::rng(),
+        })
+    }
+
+    pub fn next(&mut self) -> usize {
+        match &self.weighted {
+            Some((sizes, index)) => sizes[index.sample(&mut self.rng)],
+            None => self.block_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_block_sizes() {
+        assert_eq!(
+            parse_block_sizes("4KB:50,64KB:30,1MB:20").unwrap().sizes,
+            vec![
+                WeightedBlockSize {
+                    size: 4096,
+                    weight: 50
+                },
+                WeightedBlockSize {
+                    size: 65536,
+                    weight: 30
+                },
+                WeightedBlockSize {
+                    size: 1048576,
+                    weight: 20
+                },
+            ]
+        );
+        let range = parse_block_sizes("4KB-32KB").unwrap();
+        assert_eq!(
+            range.sizes.iter().map(|s| s.size).collect::<Vec<_>>(),
+            vec![4096, 8192, 16384, 32768]
+        );
+        assert_eq!(range.min(), 4096);
+        assert_eq!(range.max(), 32768);
+        assert!(range.is_aligned());
+
+        let unused = parse_block_sizes("512B:0,4KB:1,64KB:1").unwrap();
+        assert_eq!(unused.min(), 4096);
+        assert!(unused.is_aligned());
+        assert!(!parse_block_sizes("4KB:1,6KB:1").unwrap().is_aligned());
+        assert!(parse_block_sizes("4KB:0").is_err());
+        assert!(parse_block_sizes("1MB-4KB").is_err());
+        assert!(parse_block_sizes("4XB").is_err());
+    }
+
+    #[test]
+    fn test_block_size_picker() {
+        let sizes = parse_block_sizes("4KB:1,8KB:0").unwrap();
+        let mut picker = BlockSizePicker::new(1024, Some(&sizes)).unwrap();
+        assert!((0..100).all(|_| picker.next() == 4096));
+        let mut picker = BlockSizePicker::new(1024, None).unwrap();
+        assert_eq!(picker.next(), 1024);
+    }
+}
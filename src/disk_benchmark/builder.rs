@@ -0,0 +1,515 @@
+use std::{path::PathBuf, time::Duration};
+
+use super::{
+    AccessPattern, AdaptiveOptions, Advice, BlockSizes, CachePolicy, Engine, OffsetDistribution,
+    ReadWrite, SessionOptions, StreamOptions, SustainedOptions,
+};
+use crate::{
+    support::{DataSize, Unit},
+    Error,
+};
+
+/// Builds [`SessionOptions`] for running a benchmark from code. Start with [`SessionOptions::builder`].
+///
+/// The defaults match the command line's, except that no progress bar is shown and the test file is filled with
+/// random data.
+#[derive(Debug, Clone)]
+pub struct SessionOptionsBuilder {
+    options: SessionOptions,
+}
+
+impl SessionOptions {
+    /// A builder for benchmarking the test file at `path`.
+    pub fn builder(path: impl Into<PathBuf>) -> SessionOptionsBuilder {
+        SessionOptionsBuilder {
+            options: SessionOptions {
+                modes: vec![ReadWrite::Read, ReadWrite::Write],
+                path: path.into(),
+                file_size: Unit::GB.bytes() as usize,
+                block_size: 128 * Unit::MB.bytes() as usize,
+                cycles: 10,
+                no_create: false,
+                no_delete: false,
+                dry_run: false,
+                no_progress: true,
+                no_disable_cache: false,
+                random_seek: false,
+                no_close_file: false,
+                no_random_buffer: false,
+                compare_cache: false,
+                engine: Engine::default(),
+                madvise: None,
+                no_msync: false,
+                distribution: OffsetDistribution::default(),
+                pattern: AccessPattern::default(),
+                block_sizes: None,
+                rate_iops: None,
+                rate_bytes: None,
+                sample_interval: None,
+                sample_bytes: None,
+                sustained: None,
+                adaptive: None,
+                dashboard: false,
+                stream: None,
+            },
+        }
+    }
+}
+
+impl SessionOptionsBuilder {
+    /// The modes to run, in order.
+    pub fn modes(mut self, modes: Vec<ReadWrite>) -> Self {
+        self.options.modes = modes;
+        self
+    }
+
+    /// Size of the test file in bytes, each cycle reads or writes all of it.
+    pub fn file_size(mut self, file_size: usize) -> Self {
+        self.options.file_size = file_size;
+        self
+    }
+
+    /// Size of each read or write in bytes.
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.options.block_size = block_size;
+        self
+    }
+
+    /// Pick the size of each read or write from a distribution instead of using the block size.
+    pub fn block_sizes(mut self, block_sizes: BlockSizes) -> Self {
+        self.options.block_sizes = Some(block_sizes);
+        self
+    }
+
+    pub fn cycles(mut self, cycles: usize) -> Self {
+        self.options.cycles = cycles;
+        self
+    }
+
+    /// Run cycles until throughput reaches a steady state instead of a fixed number of cycles.
+    pub fn adaptive(mut self, adaptive: AdaptiveOptions) -> Self {
+        self.options.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Write continuously until the budget is used up instead of running cycles. Implies a single write cycle that is
+    /// sampled every second, unless [`sample_interval`](Self::sample_interval) or
+    /// [`sample_bytes`](Self::sample_bytes) say otherwise.
+    pub fn sustained(mut self, sustained: SustainedOptions) -> Self {
+        self.options.sustained = Some(sustained);
+        self.options.modes = vec![ReadWrite::Write];
+        self.options.cycles = 1;
+        self
+    }
+
+    /// Use an existing test file instead of creating one.
+    pub fn no_create(mut self, no_create: bool) -> Self {
+        self.options.no_create = no_create;
+        self
+    }
+
+    /// Keep the test file afterwards.
+    pub fn no_delete(mut self, no_delete: bool) -> Self {
+        self.options.no_delete = no_delete;
+        self
+    }
+
+    /// Create and delete the test file but don't read or write it.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.options.dry_run = dry_run;
+        self
+    }
+
+    /// Show a progress bar on stderr.
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.options.no_progress = !progress;
+        self
+    }
+
+    /// Whether runs bypass the file system cache or go through it.
+    pub fn cache(mut self, cache: CachePolicy) -> Self {
+        self.options.no_disable_cache = cache == CachePolicy::Buffered;
+        self
+    }
+
+    /// Run each mode with and without the file system cache.
+    pub fn compare_cache(mut self, compare_cache: bool) -> Self {
+        self.options.compare_cache = compare_cache;
+        self
+    }
+
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.options.engine = engine;
+        self
+    }
+
+    /// Access pattern hint for the mmap engine.
+    pub fn madvise(mut self, advice: Advice) -> Self {
+        self.options.madvise = Some(advice);
+        self
+    }
+
+    /// Whether the mmap engine flushes the mapping with `msync(2)` at the end of each write cycle, on by default.
+    pub fn msync(mut self, msync: bool) -> Self {
+        self.options.no_msync = !msync;
+        self
+    }
+
+    /// Visit random blocks, picked from `distribution`.
+    pub fn random(mut self, distribution: OffsetDistribution) -> Self {
+        self.options.random_seek = true;
+        self.options.distribution = distribution;
+        self
+    }
+
+    /// The order in which blocks are visited when not random.
+    pub fn pattern(mut self, pattern: AccessPattern) -> Self {
+        self.options.pattern = pattern;
+        self
+    }
+
+    /// Keep the test file open for all cycles of a run.
+    pub fn no_close_file(mut self, no_close_file: bool) -> Self {
+        self.options.no_close_file = no_close_file;
+        self
+    }
+
+    /// Fill the test file with zeros instead of random data.
+    pub fn no_random_buffer(mut self, no_random_buffer: bool) -> Self {
+        self.options.no_random_buffer = no_random_buffer;
+        self
+    }
+
+    /// Limit operations per second.
+    pub fn rate_iops(mut self, rate_iops: f64) -> Self {
+        self.options.rate_iops = Some(rate_iops);
+        self
+    }
+
+    /// Limit bytes per second.
+    pub fn rate_bytes(mut self, rate_bytes: usize) -> Self {
+        self.options.rate_bytes = Some(rate_bytes);
+        self
+    }
+
+    /// Sample throughput at this interval during each cycle.
+    pub fn sample_interval(mut self, interval: Duration) -> Self {
+        self.options.sample_interval = Some(interval.as_secs_f64());
+        self
+    }
+
+    /// Sample throughput every time this many bytes have been transferred during each cycle.
+    pub fn sample_bytes(mut self, bytes: usize) -> Self {
+        self.options.sample_bytes = Some(bytes);
+        self
+    }
+
+    /// Show a full screen dashboard on stdout instead of the progress bar, stdout should be a terminal.
+    pub fn dashboard(mut self, dashboard: bool) -> Self {
+        self.options.dashboard = dashboard;
+        self
+    }
+
+    /// Stream a record of each cycle and run while the benchmark runs.
+    pub fn stream(mut self, stream: StreamOptions) -> Self {
+        self.options.stream = Some(stream);
+        self
+    }
+
+    /// Rounds the file size down to a multiple of the (smallest) block size and checks that the options can be
+    /// benchmarked.
+    pub fn build(mut self) -> Result<SessionOptions, Error> {
+        if let Some(block_sizes) = &self.options.block_sizes {
+            self.options.block_size = block_sizes.min();
+        }
+        if self.options.sustained.is_some()
+            && self.options.sample_interval.is_none()
+            && self.options.sample_bytes.is_none()
+        {
+            self.options.sample_interval = Some(1.0);
+        }
+        let remainder = self.options.file_size % self.options.block_size.max(1);
+        if remainder != 0 && self.options.file_size > remainder {
+            let file_size = self.options.file_size - remainder;
+            log::warn!(
+                "File size ({}) is not divisible by block size ({}). Reducing file size to {}.",
+                DataSize::from(self.options.file_size).to_human_string(),
+                DataSize::from(self.options.block_size).to_human_string(),
+                DataSize::from(file_size).to_human_string(),
+            );
+            self.options.file_size = file_size;
+        }
+        self.options.validate()?;
+        Ok(self.options)
+    }
+
+    /// The options as set, without the adjustments and checks of [`build`](Self::build). For describing sessions
+    /// that ran elsewhere, e.g. imported from fio.
+    pub(crate) fn build_unchecked(self) -> SessionOptions {
+        self.options
+    }
+}
+
+impl SessionOptions {
+    /// Checks that the options can be benchmarked, [`Session::main`](super::Session::main) does so before creating
+    /// the test file.
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |message: String| Err(Error::InvalidOptions(message));
+        let human = |size: usize| DataSize::from(size).to_human_string();
+        if self.modes.is_empty() {
+            return invalid("No modes to run.".to_string());
+        }
+        if self.file_size == 0 || self.block_size == 0 {
+            return invalid("File size and block size must be greater than zero.".to_string());
+        }
+        let largest_block = self
+            .block_sizes
+            .as_ref()
+            .map_or(self.block_size, |b| b.max());
+        if largest_block >= self.file_size {
+            return invalid(format!(
+                "Block size ({}) must be smaller than the file size ({}).",
+                human(largest_block),
+                human(self.file_size)
+            ));
+        }
+        if let Some(block_sizes) = &self.block_sizes {
+            if !block_sizes.is_aligned() {
+                return invalid(format!(
+                    "Block sizes ({}) must all be multiples of the smallest one, so offsets stay aligned.",
+                    block_sizes
+                ));
+            }
+        }
+        if self.sustained.is_none() && self.max_cycles() < 2 {
+            return invalid("Number of cycles must be at least two.".to_string());
+        }
+        if let Some(adaptive) = &self.adaptive {
+            if adaptive.window < 2 || adaptive.max_cycles < adaptive.window {
+                return invalid(
+                    "The steady state window must be at least two cycles and no more than the maximum number of cycles."
+                        .to_string(),
+                );
+            }
+            if adaptive.target_rse.is_some_and(|rse| rse <= 0.0) {
+                return invalid(
+                    "Target relative standard error must be greater than zero.".to_string(),
+                );
+            }
+        }
+        if let Some(sustained) = &self.sustained {
+            if sustained.bytes.is_none() && sustained.duration.is_none() {
+                return invalid("A sustained write needs a budget.".to_string());
+            }
+            if self.modes != [ReadWrite::Write] {
+                return invalid("A sustained run can only write.".to_string());
+            }
+            if self.sample_interval.is_none() && self.sample_bytes.is_none() {
+                return invalid(
+                    "A sustained write needs a sample interval or sample bytes.".to_string(),
+                );
+            }
+        }
+        if self.rate_iops.is_some_and(|r| r <= 0.0) || self.rate_bytes == Some(0) {
+            return invalid("Rate limits must be greater than zero.".to_string());
+        }
+        match self.pattern {
+            AccessPattern::Stride { stride }
+                if stride < largest_block || !stride.is_multiple_of(largest_block) =>
+            {
+                return invalid(format!(
+                    "Stride ({}) must be a multiple of the block size ({}).",
+                    human(stride),
+                    human(largest_block)
+                ));
+            }
+            AccessPattern::Interleaved { streams }
+                if streams == 0 || self.file_size / streams < largest_block =>
+            {
+                return invalid(format!(
+                    "File size ({}) is too small for {} streams of block size ({}).",
+                    human(self.file_size),
+                    streams,
+                    human(largest_block)
+                ));
+            }
+            _ => {}
+        }
+        if self.madvise.is_some() && self.engine != Engine::Mmap {
+            return invalid("madvise can only be used with the mmap engine.".to_string());
+        }
+        if self.no_msync && self.engine != Engine::Mmap {
+            return invalid("msync can only be skipped with the mmap engine.".to_string());
+        }
+        if self.dashboard
+            && self
+                .stream
+                .as_ref()
+                .is_some_and(|stream| stream.path.as_os_str() == "-")
+        {
+            return invalid("Can't stream to stdout while showing the dashboard.".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disk_benchmark::Session;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_builder() {
+        let tmp_dir = tempdir().unwrap();
+        let options = SessionOptions::builder(tmp_dir.path().join("test.dat"))
+            .file_size(64 * 1024)
+            .block_size(4096)
+            .cycles(2)
+            .modes(vec![ReadWrite::Write])
+            .cache(CachePolicy::Buffered)
+            .random(OffsetDistribution::Uniform)
+            .build()
+            .unwrap();
+        assert!(options.no_progress);
+        assert!(options.no_disable_cache);
+        assert!(options.random_seek);
+
+        let result = Session::new(options).main().unwrap();
+        assert_eq!(result.runs.len(), 1);
+        assert_eq!(result.runs[0].mode, ReadWrite::Write);
+        assert_eq!(result.runs[0].cache, CachePolicy::Buffered);
+        assert_eq!(result.runs[0].cycle_results.len(), 2);
+    }
+
+    #[test]
+    fn test_builder_rounds_file_size() {
+        let tmp_dir = tempdir().unwrap();
+        let options = SessionOptions::builder(tmp_dir.path().join("test.dat"))
+            .file_size(10000)
+            .block_size(4096)
+            .cycles(2)
+            .modes(vec![ReadWrite::Write])
+            .cache(CachePolicy::Buffered)
+            .build()
+            .unwrap();
+        assert_eq!(options.file_size, 8192);
+
+        let result = Session::new(options).main().unwrap();
+        for cycle in &result.runs[0].cycle_results {
+            assert_eq!(cycle.bytes, 8192);
+        }
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let error = SessionOptions::builder("test.dat")
+            .file_size(4096)
+            .block_size(64 * 1024)
+            .build()
+            .unwrap_err();
+        assert!(matches!(error, Error::InvalidOptions(_)));
+
+        assert!(SessionOptions::builder("test.dat")
+            .cycles(1)
+            .build()
+            .is_err());
+        assert!(SessionOptions::builder("test.dat")
+            .sustained(SustainedOptions {
+                bytes: None,
+                duration: None
+            })
+            .build()
+            .is_err());
+        let options = SessionOptions::builder("test.dat")
+            .sustained(SustainedOptions {
+                bytes: Some(64 * 1024 * 1024),
+                duration: None,
+            })
+            .build()
+            .unwrap();
+        assert_eq!(options.modes, vec![ReadWrite::Write]);
+        assert_eq!(options.sample_interval, Some(1.0));
+        assert!(SessionOptions::builder("test.dat")
+            .sustained(SustainedOptions {
+                bytes: Some(64 * 1024 * 1024),
+                duration: None,
+            })
+            .modes(vec![ReadWrite::Read, ReadWrite::Write])
+            .build()
+            .is_err());
+        assert!(SessionOptions::builder("test.dat")
+            .file_size(64 * 1024)
+            .block_size(64 * 1024)
+            .build()
+            .is_err());
+        assert!(SessionOptions::builder("test.dat")
+            .file_size(64 * 1024)
+            .block_size(4096)
+            .pattern(AccessPattern::Stride { stride: 6144 })
+            .build()
+            .is_err());
+        assert!(SessionOptions::builder("test.dat")
+            .file_size(64 * 1024)
+            .block_size(4096)
+            .pattern(AccessPattern::Interleaved { streams: 32 })
+            .build()
+            .is_err());
+        assert!(SessionOptions::builder("test.dat")
+            .madvise(Advice::Sequential)
+            .build()
+            .is_err());
+        assert!(SessionOptions::builder("test.dat")
+            .dashboard(true)
+            .stream(StreamOptions {
+                path: "-".into(),
+                format: crate::disk_benchmark::StreamFormat::Jsonl,
+            })
+            .build()
+            .is_err());
+        assert!(SessionOptions::builder("test.dat")
+            .block_sizes(crate::disk_benchmark::parse_block_sizes("4KB:1,6KB:1").unwrap())
+            .build()
+            .is_err());
+        let options = SessionOptions::builder("test.dat")
+            .block_sizes(crate::disk_benchmark::parse_block_sizes("512B:0,4KB:1,64KB:1").unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(options.block_size, 4096);
+        assert!(SessionOptions::builder("test.dat").build().is_ok());
+    }
+
+    #[test]
+    fn test_session_validation() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+        let mut options = SessionOptions::builder(&file_path)
+            .file_size(64 * 1024)
+            .block_size(4096)
+            .cycles(2)
+            .build()
+            .unwrap();
+        options.block_size = options.file_size;
+        let error = Session::new(options).main().unwrap_err();
+        assert!(matches!(error, Error::InvalidOptions(_)), "{:?}", error);
+        assert!(!file_path.exists());
+    }
+
+    #[test]
+    fn test_io_error() {
+        let tmp_dir = tempdir().unwrap();
+        let options = SessionOptions::builder(tmp_dir.path().join("missing").join("test.dat"))
+            .file_size(64 * 1024)
+            .block_size(4096)
+            .cycles(2)
+            .build()
+            .unwrap();
+        let error = Session::new(options).main().unwrap_err();
+        assert!(
+            matches!(&error, Error::File(e) if e.kind == crate::ErrorKind::Open && e.cycle.is_none()),
+            "{:?}",
+            error
+        );
+        assert_eq!(error.exit_code(), 10);
+    }
+}
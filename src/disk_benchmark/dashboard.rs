@@ -0,0 +1,313 @@
+use anyhow::Result;
+use crossterm::{cursor, execute, queue, style::Print, terminal};
+use std::{
+    collections::VecDeque,
+    io::{stdout, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use super::{support::BlockDevice, FileError, Observer, RunInfo, RunResult, SessionResult};
+use crate::charts::sparkline;
+use crate::colored_markup::StyleSheet;
+use crate::support::*;
+use crate::Error;
+
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+/// Number of refreshes kept for the sparklines.
+const HISTORY: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug)]
+struct Job {
+    name: String,
+    status: JobStatus,
+    /// Mean throughput once the job is done.
+    throughput: Option<f64>,
+}
+
+#[derive(Debug)]
+struct State {
+    title: String,
+    jobs: Vec<Job>,
+    start: Instant,
+    cycle: usize,
+    cycles: usize,
+    bytes: u64,
+    total_bytes: Option<u64>,
+    // Accumulated since the last refresh.
+    tick_bytes: u64,
+    tick_ops: u64,
+    tick_latency: f64,
+    last_tick: Instant,
+    throughput: f64,
+    iops: f64,
+    throughput_history: VecDeque<f64>,
+    latency_history: VecDeque<f64>,
+    device: Option<BlockDevice>,
+    io_ticks: Option<u64>,
+    utilization: Option<f64>,
+}
+
+/// A full screen view of a session's progress: the status of each run, current throughput and IOPS, recent latency
+/// and how busy the device is. Redrawn on a background thread on the terminal's alternate screen.
+#[derive(Debug)]
+pub struct Dashboard {
+    state: Arc<Mutex<State>>,
+    stop: Arc<AtomicBool>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Dashboard {
+    pub fn start(title: String, jobs: Vec<String>, device: Option<BlockDevice>) -> Result<Self> {
+        let now = Instant::now();
+        let io_ticks = device.as_ref().and_then(|d| d.io_ticks());
+        let state = Arc::new(Mutex::new(State {
+            title,
+            jobs: jobs
+                .into_iter()
+                .map(|name| Job {
+                    name,
+                    status: JobStatus::Pending,
+                    throughput: None,
+                })
+                .collect(),
+            start: now,
+            cycle: 0,
+            cycles: 0,
+            bytes: 0,
+            total_bytes: None,
+            tick_bytes: 0,
+            tick_ops: 0,
+            tick_latency: 0.0,
+            last_tick: now,
+            throughput: 0.0,
+            iops: 0.0,
+            throughput_history: VecDeque::with_capacity(HISTORY),
+            latency_history: VecDeque::with_capacity(HISTORY),
+            device,
+            io_ticks,
+            utilization: None,
+        }));
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let state = state.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                let style_sheet =
+                    StyleSheet::parse(STYLE_SHEET).expect("Failed to parse stylesheet.");
+                while !stop.load(Ordering::Relaxed) {
+                    std::thread::sleep(REFRESH_INTERVAL);
+                    let lines = {
+                        let mut state = state.lock().unwrap();
+                        state.tick();
+                        state.lines()
+                    };
+                    if let Err(error) = draw(&style_sheet, &lines) {
+                        log::warn!("Failed to draw dashboard: {}", error);
+                    }
+                }
+            })
+        };
+        Ok(Dashboard {
+            state,
+            stop,
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    /// Stops redrawing and restores the terminal.
+    pub fn finish(&self) -> Result<()> {
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            self.stop.store(true, Ordering::Relaxed);
+            let _ = thread.join();
+            execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+        }
+        Ok(())
+    }
+}
+
+impl Observer for Dashboard {
+    fn run_started(&self, run: &RunInfo) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.jobs[run.index].status = JobStatus::Running;
+        state.bytes = 0;
+        state.total_bytes = run.total_bytes;
+        state.cycle = 0;
+        state.cycles = 0;
+        Ok(())
+    }
+
+    fn cycle_started(&self, run: &RunInfo, cycle: usize) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.cycle = cycle;
+        state.cycles = run.cycles;
+        Ok(())
+    }
+
+    fn operation(&self, _run: &RunInfo, size: usize, latency: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.bytes += size as u64;
+        state.tick_bytes += size as u64;
+        state.tick_ops += 1;
+        state.tick_latency += latency;
+    }
+
+    fn run_completed(&self, run: &RunInfo, result: &RunResult) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.jobs[run.index].status = JobStatus::Done;
+        state.jobs[run.index].throughput = Some(result.statistics.total_throughput);
+        Ok(())
+    }
+
+    fn run_failed(&self, run: &RunInfo, _error: &FileError) -> Result<()> {
+        self.state.lock().unwrap().jobs[run.index].status = JobStatus::Failed;
+        Ok(())
+    }
+
+    fn session_completed(&self, _result: &SessionResult) -> Result<()> {
+        self.finish()
+    }
+
+    /// Restores the terminal so the error can be shown.
+    fn error(&self, _error: &Error) {
+        let _ = self.finish();
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+impl State {
+    fn tick(&mut self) {
+        let now = Instant::now();
+        let elapsed = (now - self.last_tick).as_secs_f64();
+        self.throughput = self.tick_bytes as f64 / elapsed;
+        self.iops = self.tick_ops as f64 / elapsed;
+        let latency = if self.tick_ops > 0 {
+            self.tick_latency / self.tick_ops as f64
+        } else {
+            0.0
+        };
+        push(&mut self.throughput_history, self.throughput);
+        push(&mut self.latency_history, latency);
+        if let Some(device) = &self.device {
+            let io_ticks = device.io_ticks();
+            if let (Some(before), Some(after)) = (self.io_ticks, io_ticks) {
+                let busy = after.saturating_sub(before) as f64 / 1000.0;
+                self.utilization = Some((busy / elapsed).min(1.0));
+            }
+            self.io_ticks = io_ticks;
+        }
+        self.tick_bytes = 0;
+        self.tick_ops = 0;
+        self.tick_latency = 0.0;
+        self.last_tick = now;
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!(
+                "<info>{}</info>, Elapsed: <num>{}</num>",
+                self.title,
+                humantime::format_duration(Duration::from_secs(self.start.elapsed().as_secs()))
+            ),
+            String::new(),
+        ];
+        for job in self.jobs.iter() {
+            lines.push(match job.status {
+                JobStatus::Pending => format!("  <mode>{}</mode>: waiting", job.name),
+                JobStatus::Running if self.cycles > 0 => format!(
+                    "▶ <mode>{}</mode>: cycle <num>{}</num>/<num>{}</num>",
+                    job.name,
+                    self.cycle + 1,
+                    self.cycles
+                ),
+                JobStatus::Running => format!("▶ <mode>{}</mode>: running", job.name),
+                JobStatus::Done => format!(
+                    "✓ <mode>{}</mode>: <speed>{}</speed>/sec",
+                    job.name,
+                    DataSize::from(job.throughput.unwrap_or(0.0)).to_human_string()
+                ),
+                JobStatus::Failed => format!("✗ <mode>{}</mode>: failed", job.name),
+            });
+        }
+        lines.push(String::new());
+        lines.push(match self.total_bytes {
+            Some(total) if total > 0 => format!(
+                "Progress: <size>{}</size> of <size>{}</size> (<num>{:.0}%</num>)",
+                DataSize::from(self.bytes).to_human_string(),
+                DataSize::from(total).to_human_string(),
+                self.bytes as f64 / total as f64 * 100.0
+            ),
+            _ => format!(
+                "Progress: <size>{}</size>",
+                DataSize::from(self.bytes).to_human_string()
+            ),
+        });
+        lines.push(format!(
+            "Throughput: <speed>{}</speed>/sec, IOPS: <num>{:.0}</num>",
+            DataSize::from(self.throughput).to_human_string(),
+            self.iops
+        ));
+        let throughput: Vec<f64> = self.throughput_history.iter().cloned().collect();
+        lines.push(format!(
+            "Throughput: <speed>{}</speed>",
+            sparkline(&throughput)
+        ));
+        let latency: Vec<f64> = self.latency_history.iter().cloned().collect();
+        lines.push(format!(
+            "Latency:    <num>{}</num> {}",
+            sparkline(&latency),
+            format_latency(latency.last().cloned().unwrap_or(0.0))
+        ));
+        lines.push(match (&self.device, self.utilization) {
+            (Some(device), Some(utilization)) => format!(
+                "Device: <info>{}</info>, Utilization: <num>{:.0}%</num>",
+                device.name,
+                utilization * 100.0
+            ),
+            (Some(device), None) => format!("Device: <info>{}</info>", device.name),
+            (None, _) => "Device: unknown".to_string(),
+        });
+        lines
+    }
+}
+
+fn push(history: &mut VecDeque<f64>, value: f64) {
+    if history.len() == HISTORY {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+fn draw(style_sheet: &StyleSheet, lines: &[String]) -> Result<()> {
+    let mut stdout = stdout();
+    queue!(
+        stdout,
+        cursor::MoveTo(0, 0),
+        terminal::Clear(terminal::ClearType::All)
+    )?;
+    for (row, line) in lines.iter().enumerate() {
+        let line = style_sheet.render(line)?;
+        queue!(stdout, cursor::MoveTo(0, row as u16), Print(line))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Number of buckets per doubling of latency, each bucket is ~1% wide.
+const BUCKETS_PER_OCTAVE: f64 = 64.0;
+
+/// A log-bucketed histogram of operation latencies. Percentiles are accurate to about 1% without keeping every
+/// sample, which matters for long runs of small operations.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LatencyHistogram {
+    /// Counts indexed by bucket, bucket `i` holds latencies around `2^(i / BUCKETS_PER_OCTAVE)` nanoseconds.
+    counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl LatencyHistogram {
+    /// Records a latency in seconds.
+    pub fn record(&mut self, latency: f64) {
+        let bucket = Self::bucket(latency);
+        if bucket >= self.counts.len() {
+            self.counts.resize(bucket + 1, 0);
+        }
+        self.counts[bucket] += 1;
+        if self.count == 0 || latency < self.min {
+            self.min = latency;
+        }
+        if self.count == 0 || latency > self.max {
+            self.max = latency;
+        }
+        self.count += 1;
+        self.sum += latency;
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if other.count == 0 {
+            return;
+        }
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other;
+        }
+        self.min = if self.count == 0 {
+            other.min
+        } else {
+            self.min.min(other.min)
+        };
+        self.max = self.max.max(other.max);
+        self.count += other.count;
+        self.sum += other.sum;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the lower bound latency (in seconds) and count of every non-empty bucket, in ascending order.
+    pub fn buckets(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| (Self::lower_bound(bucket).clamp(self.min, self.max), *count))
+    }
+
+    /// Returns the latency (in seconds) below which `fraction` of the samples fall.
+    pub fn percentile(&self, fraction: f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let rank = (fraction * self.count as f64).ceil().max(1.0) as u64;
+        if rank <= 1 {
+            return self.min;
+        }
+        if rank >= self.count {
+            return self.max;
+        }
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Self::value(bucket).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    fn bucket(latency: f64) -> usize {
+        let nanoseconds = (latency * 1e9).max(1.0);
+        (nanoseconds.log2() * BUCKETS_PER_OCTAVE) as usize
+    }
+
+    fn lower_bound(bucket: usize) -> f64 {
+        2_f64.powf(bucket as f64 / BUCKETS_PER_OCTAVE) / 1e9
+    }
+
+    fn value(bucket: usize) -> f64 {
+        // Midpoint of the bucket.
+        2_f64.powf((bucket as f64 + 0.5) / BUCKETS_PER_OCTAVE) / 1e9
+    }
+}
+
+/// Summary of operation latencies in seconds.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct LatencyStatistics {
+    pub mean: f64,
+    pub min: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub p999: f64,
+    pub max: f64,
+}
+
+impl LatencyStatistics {
+    pub fn new(histogram: &LatencyHistogram) -> Option<Self> {
+        if histogram.is_empty() {
+            return None;
+        }
+        Some(LatencyStatistics {
+            mean: histogram.sum / histogram.count as f64,
+            min: histogram.min,
+            p50: histogram.percentile(0.5),
+            p90: histogram.percentile(0.9),
+            p99: histogram.percentile(0.99),
+            p999: histogram.percentile(0.999),
+            max: histogram.max,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_percentiles() {
+        let mut histogram = LatencyHistogram::default();
+        for i in 1..=1000 {
+            histogram.record(i as f64 * 1e-6);
+        }
+        assert_eq!(histogram.count, 1000);
+        let assert_near = |value: f64, expected: f64| {
+            assert!(
+                (value - expected).abs() / expected < 0.02,
+                "{} != {}",
+                value,
+                expected
+            )
+        };
+        assert_near(histogram.percentile(0.5), 500e-6);
+        assert_near(histogram.percentile(0.99), 990e-6);
+        assert_eq!(histogram.percentile(1.0), 1000e-6);
+        assert_eq!(histogram.percentile(0.0), 1e-6);
+
+        let statistics = LatencyStatistics::new(&histogram).unwrap();
+        assert_near(statistics.mean, 500.5e-6);
+        assert_eq!(statistics.min, 1e-6);
+        assert_eq!(statistics.max, 1000e-6);
+    }
+
+    #[test]
+    fn test_histogram_merge() {
+        let mut a = LatencyHistogram::default();
+        a.record(0.001);
+        let mut b = LatencyHistogram::default();
+        b.record(0.002);
+        b.record(0.0005);
+        a.merge(&b);
+        assert_eq!(a.count, 3);
+        assert_eq!(a.percentile(0.0), 0.0005);
+        assert_eq!(a.percentile(1.0), 0.002);
+        assert!(LatencyStatistics::new(&LatencyHistogram::default()).is_none());
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Result};
+use std::fs::File;
+
+use super::Advice;
+
+/// A shared, read/write memory mapping of the first `len` bytes of a file.
+pub struct MappedFile {
+    ptr: *mut u8,
+    len: usize,
+}
+
+// MARK: Unix
+
+#[cfg(unix)]
+use std::os::fd::AsRawFd;
+
+#[cfg(unix)]
+impl MappedFile {
+    pub fn map(file: &File, len: usize) -> Result<Self> {
+        log::debug!("Mapping {} bytes using mmap", len);
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(MappedFile {
+            ptr: ptr as *mut u8,
+            len,
+        })
+    }
+
+    pub fn advise(&self, advice: &Advice) -> Result<()> {
+        let advice = match advice {
+            Advice::Normal => libc::MADV_NORMAL,
+            Advice::Sequential => libc::MADV_SEQUENTIAL,
+            Advice::Random => libc::MADV_RANDOM,
+            Advice::WillNeed => libc::MADV_WILLNEED,
+            Advice::DontNeed => libc::MADV_DONTNEED,
+        };
+        log::debug!("Setting madvise({}) on mapping", advice);
+        let r = unsafe { libc::madvise(self.ptr as *mut libc::c_void, self.len, advice) };
+        if r == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    pub fn sync(&self) -> Result<()> {
+        let r = unsafe { libc::msync(self.ptr as *mut libc::c_void, self.len, libc::MS_SYNC) };
+        if r == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+// MARK: Windows
+
+#[cfg(target_os = "windows")]
+impl MappedFile {
+    pub fn map(_: &File, _: usize) -> Result<Self> {
+        Err(anyhow!(
+            "The mmap engine is not supported on this platform."
+        ))
+    }
+
+    pub fn advise(&self, _: &Advice) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// MARK: -
+
+impl MappedFile {
+    pub fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        let range = self.range(offset, buffer.len())?;
+        buffer.copy_from_slice(&self.as_slice()[range]);
+        Ok(())
+    }
+
+    pub fn write(&mut self, offset: usize, buffer: &[u8]) -> Result<()> {
+        let range = self.range(offset, buffer.len())?;
+        self.as_mut_slice()[range].copy_from_slice(buffer);
+        Ok(())
+    }
+
+    fn range(&self, offset: usize, len: usize) -> Result<std::ops::Range<usize>> {
+        if offset + len > self.len {
+            return Err(anyhow!(
+                "Access of {} bytes at offset {} is outside the {} byte mapping.",
+                len,
+                offset,
+                self.len
+            ));
+        }
+        Ok(offset..offset + len)
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempfile;
+
+    #[test]
+    fn test_mapped_file() {
+        let mut file = tempfile().unwrap();
+        file.write_all(&[0xAB; 8192]).unwrap();
+
+        let mut map = MappedFile::map(&file, 8192).unwrap();
+        map.advise(&Advice::Sequential).unwrap();
+
+        let mut buffer = [0; 4096];
+        map.read(4096, &mut buffer).unwrap();
+        assert!(buffer.iter().all(|b| *b == 0xAB));
+
+        map.write(0, &[0xCD; 4096]).unwrap();
+        map.sync().unwrap();
+        map.read(0, &mut buffer).unwrap();
+        assert!(buffer.iter().all(|b| *b == 0xCD));
+
+        assert!(map.read(8000, &mut buffer).is_err());
+    }
+}
@@ -1,35 +1,110 @@
 use anyhow::{Ok, Result};
 use enum_display_derive::Display;
-use indicatif::{ProgressBar, ProgressStyle};
-use rand::{Rng, RngCore};
+use rand::RngCore;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fmt::Display,
     fs::File,
     io::{Read, Seek, Write},
     path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     vec,
 };
 
+mod access;
+mod block_sizes;
+mod builder;
+mod dashboard;
+mod latency;
+mod mmap;
+mod observer;
+mod pacer;
+mod progress;
+mod sampling;
+mod schema;
+mod statistics;
+mod steady_state;
+mod stream;
 mod support;
+mod sustained;
+use access::*;
+pub use access::{
+    parse_access_pattern, parse_offset_distribution, AccessPattern, OffsetDistribution,
+};
+use block_sizes::*;
+pub use block_sizes::{parse_block_sizes, BlockSizes};
+pub use builder::SessionOptionsBuilder;
+use dashboard::*;
+pub use latency::{LatencyHistogram, LatencyStatistics};
+use mmap::*;
+use observer::*;
+pub use observer::{Observer, RunInfo};
+use pacer::*;
+use progress::*;
+pub use sampling::ThroughputSample;
+use sampling::*;
+use schema::*;
+pub use schema::{read_reports, report_schema, SCHEMA_VERSION};
+pub use statistics::ConfidenceInterval;
+use statistics::*;
+pub use steady_state::{AdaptiveOptions, Convergence};
+use stream::*;
+pub use stream::{StreamFormat, StreamOptions};
 use support::*;
+pub use sustained::{SustainedAnalysis, SustainedOptions};
 
+use crate::error::{ErrorKind, FileContext, FileError};
 use crate::support::*;
 use crate::volume::*;
 
 // MARK: -
 
-#[derive(Display, PartialEq, Debug, Clone, Deserialize, Serialize)]
+#[derive(Display, PartialEq, Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub enum ReadWrite {
     Read,
     Write,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// How the benchmark reads from and writes to the test file.
+#[derive(Display, PartialEq, Debug, Clone, Copy, Default, Deserialize, JsonSchema, Serialize)]
+pub enum Engine {
+    /// `read(2)`/`write(2)` on the file descriptor.
+    #[default]
+    Syscall,
+    /// Copy in and out of a shared memory mapping of the file.
+    Mmap,
+}
+
+/// Access pattern hint passed to `madvise(2)` by the mmap engine.
+#[derive(Display, PartialEq, Debug, Clone, Copy, Deserialize, JsonSchema, Serialize)]
+pub enum Advice {
+    Normal,
+    Sequential,
+    Random,
+    WillNeed,
+    DontNeed,
+}
+
+/// Whether a run bypasses the file system cache (direct I/O) or goes through it (buffered I/O).
+#[derive(Display, PartialEq, Debug, Clone, Copy, Default, Deserialize, JsonSchema, Serialize)]
+pub enum CachePolicy {
+    #[default]
+    Direct,
+    Buffered,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone)]
 pub struct SessionOptions {
     pub modes: Vec<ReadWrite>, // TODO: Make ref?
     pub path: PathBuf,         // TODO: Make ref?
     pub file_size: usize,
+    /// Size of each read or write. With `block_sizes`, the smallest size of the distribution, which offsets are
+    /// aligned to.
     pub block_size: usize,
     pub cycles: usize,
     pub no_create: bool,
@@ -40,21 +115,89 @@ pub struct SessionOptions {
     pub random_seek: bool,
     pub no_close_file: bool,
     pub no_random_buffer: bool,
+    #[serde(default)]
+    pub compare_cache: bool,
+    #[serde(default)]
+    pub engine: Engine,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub madvise: Option<Advice>,
+    /// Don't `msync(2)` the mapping after each write cycle of the mmap engine, so the cycle ends while the writes may
+    /// still be in the page cache.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_msync: bool,
+    #[serde(default)]
+    pub distribution: OffsetDistribution,
+    #[serde(default)]
+    pub pattern: AccessPattern,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_sizes: Option<BlockSizes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_iops: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_bytes: Option<usize>,
+    /// Seconds between throughput samples.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_interval: Option<f64>,
+    /// Bytes between throughput samples.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_bytes: Option<usize>,
+    /// Write continuously until the budget is used up instead of running cycles of `file_size` bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sustained: Option<SustainedOptions>,
+    /// Run cycles until throughput reaches a steady state instead of a fixed number of cycles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adaptive: Option<AdaptiveOptions>,
+    /// Show a full screen dashboard instead of the progress bar.
+    #[serde(default)]
+    pub dashboard: bool,
+    /// Stream a record of each cycle and run while the benchmark runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<StreamOptions>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// The results of a session, the reports written by `--export-json` are arrays of these.
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 pub struct SessionResult {
+    /// Version of the report's format, see `SCHEMA_VERSION`.
+    pub schema_version: u32,
+    /// The command line arguments.
     pub args: String,
     pub created: std::time::SystemTime,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// The tool that produced the results if they were imported, e.g. `fio-3.33`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Details of the volume holding the test file, the fields depend on the operating system.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_volume"
+    )]
+    #[schemars(with = "Option<serde_json::Map<String, serde_json::Value>>")]
     pub volume: Option<Volume>,
+    /// The session was interrupted, its runs hold the cycles completed until then.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interrupted: bool,
     pub options: SessionOptions,
     pub runs: Vec<RunResult>,
+    /// Runs that failed, the session went on with the next run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<RunFailure>,
 }
 
-#[derive(Debug)]
 pub struct Session {
     pub options: SessionOptions,
+    observers: Vec<Box<dyn Observer>>,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Session")
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
 }
 
 // MARK: -
@@ -63,13 +206,36 @@ pub struct Session {
 pub struct RunOptions<'a> {
     pub session_options: &'a SessionOptions,
     pub mode: &'a ReadWrite,
+    pub cache: CachePolicy,
+    pub info: &'a RunInfo,
+    pub observer: &'a Observers<'a>,
+    pub interrupted: &'a AtomicBool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 pub struct RunResult {
     pub mode: ReadWrite,
+    #[serde(default)]
+    pub cache: CachePolicy,
     pub cycle_results: Vec<CycleResult>,
     pub statistics: RunStatistics,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sustained: Option<SustainedAnalysis>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub convergence: Option<Convergence>,
+}
+
+/// Why a run failed, see [`FileError`].
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct RunFailure {
+    pub mode: ReadWrite,
+    pub cache: CachePolicy,
+    pub kind: ErrorKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cycle: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -83,14 +249,48 @@ pub struct Run<'a> {
 pub struct CycleOptions<'a> {
     pub cycle: usize,
     pub run_options: &'a RunOptions<'a>,
-    pub progress: &'a Option<ProgressBar>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 pub struct CycleResult {
     pub cycle: usize,
     pub bytes: usize,
     pub elapsed: f64,
+    #[serde(default)]
+    pub ops: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_faults: Option<PageFaults>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub block_sizes: Vec<BlockSizeResult>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub samples: Vec<ThroughputSample>,
+    #[serde(skip)]
+    pub latencies: LatencyHistogram,
+}
+
+/// The operations of a cycle that used one particular block size. `elapsed` is the sum of the operations' durations.
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct BlockSizeResult {
+    pub block_size: usize,
+    pub ops: usize,
+    pub bytes: usize,
+    pub elapsed: f64,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema, Serialize)]
+pub struct PageFaults {
+    pub minor: u64,
+    pub major: u64,
+}
+
+impl PageFaults {
+    fn between(before: Option<(u64, u64)>, after: Option<(u64, u64)>) -> Option<Self> {
+        let ((minor_before, major_before), (minor_after, major_after)) = (before?, after?);
+        Some(PageFaults {
+            minor: minor_after.saturating_sub(minor_before),
+            major: major_after.saturating_sub(major_before),
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -98,10 +298,141 @@ pub struct Cycle<'a> {
     pub options: &'a CycleOptions<'a>,
 }
 
+/// Per-run state that decides the offset and size of each operation.
+#[derive(Debug)]
+struct Workload {
+    offsets: Offsets,
+    sizes: BlockSizePicker,
+}
+
+/// Running totals of the operations performed in a cycle.
+#[derive(Debug, Default)]
+struct Tally {
+    ops: usize,
+    bytes: usize,
+    block_sizes: BTreeMap<usize, BlockSizeResult>,
+    latencies: LatencyHistogram,
+    samples: Vec<ThroughputSample>,
+}
+
+impl Tally {
+    /// `elapsed` is how long the operation took, `latency` also includes any time it was overdue.
+    fn record(&mut self, size: usize, elapsed: f64, latency: f64) {
+        self.ops += 1;
+        self.bytes += size;
+        self.latencies.record(latency);
+        let block_size = self.block_sizes.entry(size).or_insert(BlockSizeResult {
+            block_size: size,
+            ..Default::default()
+        });
+        block_size.ops += 1;
+        block_size.bytes += size;
+        block_size.elapsed += elapsed;
+    }
+}
+
 // MARK: -
 
 impl Session {
-    pub fn main(&self) -> Result<SessionResult> {
+    pub fn new(options: SessionOptions) -> Self {
+        Session {
+            options,
+            observers: Vec::new(),
+            interrupted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// A flag that stops the session when set, e.g. from a signal handler. The current operation completes, the
+    /// cycle it belongs to is discarded (unless it's a sustained write) and no further runs start. The test file is
+    /// cleaned up as usual and the result, flagged as interrupted, holds the completed cycles.
+    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+        self.interrupted.clone()
+    }
+
+    /// Adds an observer that is told about the session's progress and results, after the progress bar, dashboard and
+    /// stream the options ask for.
+    pub fn observe(mut self, observer: impl Observer + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Runs the benchmark: creates the test file, performs a run for each mode (and cache policy) and deletes the file
+    /// again, also when the session fails, unless the options say otherwise.
+    ///
+    /// A run that fails with a [`FileError`] is recorded in the result's `failures` and the session goes on with the
+    /// next run, any other error ends the session.
+    pub fn main(&self) -> std::result::Result<SessionResult, crate::Error> {
+        let runs = self.runs();
+        let mut built_in: Vec<Box<dyn Observer>> = vec![Box::new(Logger)];
+        let started = self
+            .options
+            .validate()
+            .and_then(|_| self.built_in_observers(&runs).map_err(crate::Error::from))
+            .map(|observers| built_in.extend(observers));
+        let observer = Observers(
+            built_in
+                .iter()
+                .chain(self.observers.iter())
+                .map(|o| o.as_ref())
+                .collect(),
+        );
+        started
+            .and_then(|_| self.perform(&observer, &runs).map_err(crate::Error::from))
+            .inspect_err(|error| observer.error(error))
+    }
+
+    /// The runs the options ask for: each mode with each cache policy.
+    fn runs(&self) -> Vec<RunInfo> {
+        let cache_policies = if self.options.compare_cache {
+            vec![CachePolicy::Direct, CachePolicy::Buffered]
+        } else if self.options.no_disable_cache {
+            vec![CachePolicy::Buffered]
+        } else {
+            vec![CachePolicy::Direct]
+        };
+
+        let total_bytes = (self.options.cycle_bytes() != usize::MAX)
+            .then(|| self.options.cycle_bytes() as u64 * self.options.max_cycles() as u64);
+        self.options
+            .modes
+            .iter()
+            .flat_map(|mode| cache_policies.iter().map(move |cache| (mode, *cache)))
+            .enumerate()
+            .map(|(index, (mode, cache))| RunInfo {
+                index,
+                mode: mode.clone(),
+                cache,
+                cycles: self.options.max_cycles(),
+                total_bytes,
+            })
+            .collect()
+    }
+
+    /// The dashboard or progress bar and the result stream the options ask for. These start before the test file is
+    /// created, so the dashboard's device is resolved from the file's parent directory on a fresh run.
+    fn built_in_observers(&self, runs: &[RunInfo]) -> Result<Vec<Box<dyn Observer>>> {
+        let mut observers: Vec<Box<dyn Observer>> = Vec::new();
+        if self.options.dashboard {
+            let names = runs
+                .iter()
+                .map(|run| format!("{} ({})", run.mode, run.cache))
+                .collect();
+            observers.push(Box::new(Dashboard::start(
+                self.options.path.display().to_string(),
+                names,
+                BlockDevice::for_path(&self.options.path),
+            )?));
+        } else if !self.options.no_progress {
+            observers.push(Box::new(Progress::new(self.options.compare_cache)));
+        }
+        if let Some(stream) = &self.options.stream {
+            observers.push(Box::new(ResultStream::open(stream, &self.options.path)?));
+        }
+        Ok(observers)
+    }
+
+    fn perform(&self, observer: &Observers, runs: &[RunInfo]) -> Result<SessionResult> {
+        let test_file = TestFile::new(&self.options);
         let file = self.prepare_file(
             &self.options.path,
             self.options.file_size,
@@ -110,47 +441,67 @@ impl Session {
         )?;
         drop(file);
 
-        let runs_results: Vec<RunResult> = self
-            .options
-            .modes
-            .iter()
-            .map(|mode| {
-                let run_options = RunOptions {
-                    session_options: &self.options,
-                    mode,
-                };
-                let run = Run {
-                    options: &run_options,
-                };
-
-                run.main().expect("TODO")
-            })
-            .collect();
+        observer.session_started(&self.options, runs);
+
+        let mut runs_results: Vec<RunResult> = Vec::with_capacity(runs.len());
+        let mut failures: Vec<RunFailure> = Vec::new();
+        for info in runs.iter() {
+            if self.interrupted.load(Ordering::Relaxed) {
+                break;
+            }
+            let run_options = RunOptions {
+                session_options: &self.options,
+                mode: &info.mode,
+                cache: info.cache,
+                info,
+                observer,
+                interrupted: &self.interrupted,
+            };
+            let run = Run {
+                options: &run_options,
+            };
+            let result = run.main();
+            // The test file is still usable after a failed operation, so carry on with the next run.
+            if let Some(error) = result
+                .as_ref()
+                .err()
+                .and_then(|e| e.downcast_ref::<FileError>())
+            {
+                observer.run_failed(info, error);
+                failures.push(RunFailure {
+                    mode: info.mode.clone(),
+                    cache: info.cache,
+                    kind: error.kind,
+                    message: error.to_string(),
+                    cycle: error.cycle,
+                    offset: error.offset,
+                });
+                continue;
+            }
+            if let Some(result) = result? {
+                runs_results.push(result);
+            }
+        }
+        let interrupted = self.interrupted.load(Ordering::Relaxed);
+        if interrupted {
+            log::debug!(target: "Session", "Interrupted, keeping {} runs.", runs_results.len());
+        }
         let result = SessionResult {
+            schema_version: SCHEMA_VERSION,
             args: std::env::args().collect::<Vec<String>>()[1..].join(" "),
             volume: Volume::volume_for_path(&self.options.path).ok(),
             created: std::time::SystemTime::now(),
+            host: hostname(),
+            source: None,
+            interrupted,
             options: self.options.clone(),
 
             runs: runs_results,
+            failures,
         };
+        observer.session_completed(&result);
 
-        if !self.options.no_delete {
-            if !self.options.no_create {
-                log::debug!(
-                    target: "Session",
-                    "Deleting test file {}.",
-                    self.options.path.display()
-                );
-                std::fs::remove_file(&self.options.path)?;
-            } else {
-                log::debug!(
-                    target: "Session",
-                    "Not deleting test file {} due to --no-delete option.",
-                    self.options.path.display()
-                );
-            }
-        }
+        test_file.delete()?;
 
         Ok(result)
     }
@@ -182,7 +533,7 @@ impl Session {
                     "Deleting existing file {}.",
                     path.display()
                 );
-                std::fs::remove_file(path)?;
+                std::fs::remove_file(path).file_context(ErrorKind::Io, path)?;
             }
         }
         log::trace!(
@@ -190,7 +541,8 @@ impl Session {
             "Creating file {}.",
             path.display()
         );
-        let mut file = File::create_for_benchmarking(path, self.options.no_disable_cache)?;
+        let mut file = File::create_for_benchmarking(path, self.options.no_disable_cache)
+            .file_context(ErrorKind::Open, path)?;
         log::debug!(
             target: "Session",
             "Writing {} bytes to {}",
@@ -213,7 +565,7 @@ impl Session {
                 if cfg!(debug_assertions) {
                     log::warn!("This can take a long time in debug builds. Make sure you're running in a release build.");
                 }
-                let mut rng = rand::thread_rng();
+                let mut rng = rand::rng();
                 rng.fill_bytes(&mut buffer);
             } else {
                 log::trace!(
@@ -234,12 +586,19 @@ impl Session {
                 target: "Session",
                 "Writing buffer.",
             );
-            let bytes_written = file.write(&buffer)?;
-            anyhow::ensure!(
-                bytes_written == file_size,
-                "Failed to write all bytes to file.",
-            );
-            file.sync_all()?;
+            let bytes_written = file
+                .write(&buffer)
+                .file_context(ErrorKind::Io, path)
+                .direct_io(!self.options.no_disable_cache)?;
+            if bytes_written != file_size {
+                return Err(FileError::with_detail(
+                    ErrorKind::ShortTransfer,
+                    path,
+                    format!("Wrote {} bytes, expected {}.", bytes_written, file_size),
+                )
+                .into());
+            }
+            file.sync_all().file_context(ErrorKind::Io, path)?;
             Ok(())
         });
 
@@ -256,98 +615,258 @@ impl Session {
     }
 }
 
-impl Run<'_> {
-    pub fn main(&self) -> Result<RunResult> {
-        log::debug!(target: "Session::Run", "Starting run.");
-        let session_options = &self.options.session_options;
+/// Deletes the test file when dropped, so it's cleaned up when the session fails, unless the options keep it.
+struct TestFile<'a> {
+    path: &'a PathBuf,
+    delete: bool,
+}
+
+impl<'a> TestFile<'a> {
+    fn new(options: &'a SessionOptions) -> Self {
+        TestFile {
+            path: &options.path,
+            delete: !options.no_delete && !options.no_create,
+        }
+    }
 
-        let mut progress: Option<ProgressBar> = None;
-        if !session_options.no_progress {
-            let p = ProgressBar::new((session_options.file_size * session_options.cycles) as u64);
-            p.set_style(ProgressStyle::with_template("{prefix:5.green} {spinner} {elapsed_precise} / {eta_precise} {bar:50.green/white} {bytes:9} {msg}")
-            .expect("Failed to create progress style.")
-            .progress_chars("#-"),
+    /// Deletes the file now, failing if that doesn't work.
+    fn delete(mut self) -> Result<()> {
+        if std::mem::take(&mut self.delete) {
+            log::debug!(
+                target: "Session",
+                "Deleting test file {}.",
+                self.path.display()
+            );
+            std::fs::remove_file(self.path).file_context(ErrorKind::Io, self.path)?;
+        } else {
+            log::debug!(
+                target: "Session",
+                "Keeping test file {} due to --no-delete or --no-create option.",
+                self.path.display()
             );
-            p.set_prefix(format!("{}", self.options.mode));
-            progress = Some(p);
         }
+        Ok(())
+    }
+}
+
+impl Drop for TestFile<'_> {
+    fn drop(&mut self) {
+        if !self.delete {
+            return;
+        }
+        log::debug!(
+            target: "Session",
+            "Deleting test file {} after an error.",
+            self.path.display()
+        );
+        if let Err(error) = std::fs::remove_file(self.path) {
+            if error.kind() != std::io::ErrorKind::NotFound {
+                log::warn!(
+                    "Failed to delete test file {}: {}",
+                    self.path.display(),
+                    error
+                );
+            }
+        }
+    }
+}
+
+impl Run<'_> {
+    /// Performs the run's cycles. Returns nothing if it was interrupted before a cycle completed.
+    pub fn main(&self) -> Result<Option<RunResult>> {
+        let session_options = &self.options.session_options;
+        let observer = self.options.observer;
+        observer.run_started(self.options.info);
 
-        let mut buffer = vec![0; session_options.block_size];
+        let buffer_size = session_options
+            .block_sizes
+            .as_ref()
+            .map_or(session_options.block_size, |b| b.max());
+        let mut buffer = AlignedBuffer::new(buffer_size);
 
         if self.options.mode == &ReadWrite::Write {
-            let mut rng = rand::thread_rng();
+            let mut rng = rand::rng();
             rng.fill_bytes(&mut buffer);
         }
 
+        let path = &session_options.path;
+        let len = std::fs::metadata(path)
+            .file_context(ErrorKind::Open, path)?
+            .len();
+        if len < session_options.file_size as u64 {
+            return Err(FileError::with_detail(
+                ErrorKind::Verification,
+                path,
+                format!(
+                    "The file is {} bytes, expected at least {}.",
+                    len, session_options.file_size
+                ),
+            )
+            .into());
+        }
+
         let mut file = None;
         if session_options.no_close_file {
             log::debug!(target: "Session::Run","Opening file _once_ for this run due to --no-close-file option.");
-            file = Some(File::open_for_benchmarking(
-                &session_options.path,
-                session_options.no_disable_cache,
-            )?)
+            file = Some(
+                File::open_for_benchmarking(path, self.options.no_disable_cache())
+                    .file_context(ErrorKind::Open, path)?,
+            )
         }
 
-        let mut results = Vec::with_capacity(session_options.cycles);
+        let (alignment, max_size) = match &session_options.block_sizes {
+            Some(block_sizes) => (block_sizes.min(), block_sizes.max()),
+            None => (session_options.block_size, session_options.block_size),
+        };
+        let mut workload = Workload {
+            offsets: Offsets::new(
+                session_options.file_size,
+                alignment,
+                max_size,
+                &session_options.pattern,
+                session_options
+                    .random_seek
+                    .then_some(&session_options.distribution),
+            ),
+            sizes: BlockSizePicker::new(
+                session_options.block_size,
+                session_options.block_sizes.as_ref(),
+            )?,
+        };
 
-        for cycle_index in 0..session_options.cycles {
+        let mut results = Vec::with_capacity(session_options.max_cycles());
+        let mut convergence = None;
+
+        for cycle_index in 0..session_options.max_cycles() {
             let cycle_options = CycleOptions {
                 cycle: cycle_index,
                 run_options: self.options,
-                progress: &progress,
             };
             let cycle = Cycle {
                 options: &cycle_options,
             };
 
-            let cycle_result = cycle.main(&file, &mut buffer);
-            results.push(cycle_result?);
+            let cycle_result = cycle
+                .main(&file, &mut buffer, &mut workload)
+                .located(None, Some(cycle_index))
+                .direct_io(self.options.cache == CachePolicy::Direct)?;
+            if self.options.interrupted.load(Ordering::Relaxed) {
+                // A sustained write is a single cycle, what was written until the interruption is its result.
+                if session_options.sustained.is_some() && cycle_result.ops > 0 {
+                    observer.cycle_completed(self.options.info, &cycle_result);
+                    results.push(cycle_result);
+                }
+                log::debug!(target: "Session::Run", "Interrupted after {} complete cycles.", results.len());
+                break;
+            }
+            observer.cycle_completed(self.options.info, &cycle_result);
+            results.push(cycle_result);
+
+            if let Some(adaptive) = &session_options.adaptive {
+                let throughputs: Vec<f64> =
+                    results.iter().map(|r| r.bytes as f64 / r.elapsed).collect();
+                let current = Convergence::new(&throughputs, adaptive);
+                log::debug!(target: "Session::Run", "Cycle {}: steady state: {}, relative standard error: {:.4}.", cycle_index + 1, current.steady_state, current.relative_standard_error);
+                let converged = current.converged;
+                convergence = Some(current);
+                if converged {
+                    break;
+                }
+            }
+        }
+        if results.is_empty() {
+            return Ok(None);
+        }
+        let mut result = RunResult::new(self.options.mode.to_owned(), self.options.cache, results);
+        result.convergence = convergence;
+        if session_options.sustained.is_some() {
+            let samples: Vec<ThroughputSample> = result
+                .cycle_results
+                .iter()
+                .flat_map(|c| c.samples.iter().cloned())
+                .collect();
+            result.sustained = SustainedAnalysis::new(&samples);
         }
+        observer.run_completed(self.options.info, &result);
+        Ok(Some(result))
+    }
+}
 
-        let result = RunResult::new(self.options.mode.to_owned(), results);
-        log::debug!(target: "Session::Run","Ending run.");
-        Ok(result)
+impl SessionOptions {
+    /// The number of cycles a run performs at most.
+    pub fn max_cycles(&self) -> usize {
+        match &self.adaptive {
+            Some(adaptive) => adaptive.max_cycles,
+            None => self.cycles,
+        }
+    }
+
+    /// The most bytes each cycle transfers, or the byte budget of a sustained write (unlimited if it only has a
+    /// duration).
+    fn cycle_bytes(&self) -> usize {
+        match &self.sustained {
+            Some(sustained) => sustained.bytes.unwrap_or(usize::MAX),
+            None => self.file_size,
+        }
+    }
+}
+
+impl RunOptions<'_> {
+    fn no_disable_cache(&self) -> bool {
+        self.cache == CachePolicy::Buffered
     }
 }
 
 impl RunResult {
-    fn new(mode: ReadWrite, cycle_results: Vec<CycleResult>) -> Self {
+    fn new(mode: ReadWrite, cache: CachePolicy, cycle_results: Vec<CycleResult>) -> Self {
         let statistics = RunStatistics::new(&cycle_results);
         RunResult {
             mode,
+            cache,
             cycle_results,
             statistics,
+            sustained: None,
+            convergence: None,
         }
     }
 }
 
 impl<'a> Cycle<'a> {
-    fn main(&self, file: &'a Option<File>, buffer: &'a mut [u8]) -> Result<CycleResult> {
+    fn main(
+        &self,
+        file: &'a Option<File>,
+        buffer: &'a mut [u8],
+        workload: &mut Workload,
+    ) -> Result<CycleResult> {
         let run_options = &self.options.run_options;
         let session_options = &run_options.session_options;
-        log::debug!(target: "Session::Run::Cycle", "Starting cycle {}/{}.", self.options.cycle + 1, session_options.cycles);
-
-        assert!(session_options.file_size > session_options.block_size);
 
         let my_file: Option<File> = match file {
             Some(_) => None,
-            None => Some(File::open_for_benchmarking(
-                &session_options.path,
-                session_options.no_disable_cache,
-            )?),
+            None => Some(
+                File::open_for_benchmarking(&session_options.path, run_options.no_disable_cache())
+                    .file_context(ErrorKind::Open, &session_options.path)?,
+            ),
         };
 
-        let mut file: &File = match file {
+        let file: &File = match file {
             Some(file) => file,
             None => my_file.as_ref().unwrap(),
         };
 
-        if let Some(progress) = self.options.progress {
-            progress.inc(0);
-        }
+        run_options
+            .observer
+            .cycle_started(run_options.info, self.options.cycle);
 
-        let ops = session_options.file_size / session_options.block_size;
-        log::debug!(target: "Session::Run::Cycle", "Performing {} {} operations of {} bytes each.", ops, run_options.mode, DataSize::new(session_options.block_size, Unit::B).to_human_string());
+        match &session_options.block_sizes {
+            Some(block_sizes) => {
+                log::debug!(target: "Session::Run::Cycle", "Performing {} operations of {} totalling {}.", run_options.mode, block_sizes, DataSize::from(session_options.file_size).to_human_string());
+            }
+            None => {
+                let ops = session_options.file_size / session_options.block_size;
+                log::debug!(target: "Session::Run::Cycle", "Performing {} {} operations of {} bytes each.", ops, run_options.mode, DataSize::new(session_options.block_size, Unit::B).to_human_string());
+            }
+        }
 
         if session_options.dry_run {
             log::debug!(target: "Session::Run::Cycle", "Dry run, skipping read/write.");
@@ -355,65 +874,202 @@ impl<'a> Cycle<'a> {
                 cycle: self.options.cycle,
                 bytes: session_options.file_size,
                 elapsed: 1.0,
+                ops: session_options.file_size / session_options.block_size,
+                page_faults: None,
+                block_sizes: Vec::new(),
+                samples: Vec::new(),
+                latencies: LatencyHistogram::default(),
             });
         }
-        let (elapsed, _) = measure(|| -> Result<()> {
-            if session_options.random_seek {
-                let random_seek_location = rand::thread_rng()
-                    .gen_range(0..session_options.file_size - session_options.block_size);
-                file.seek(std::io::SeekFrom::Start(random_seek_location as u64))?;
+        let mut mapping = match session_options.engine {
+            Engine::Syscall => None,
+            Engine::Mmap => {
+                let mapping = MappedFile::map(file, session_options.file_size)
+                    .file_context(ErrorKind::Io, &session_options.path)?;
+                if let Some(advice) = &session_options.madvise {
+                    mapping.advise(advice)?;
+                }
+                Some(mapping)
             }
+        };
 
-            match run_options.mode {
-                ReadWrite::Read => {
-                    for _ in 0..ops {
-                        let count = file.read(buffer)?;
-                        if count != buffer.len() {
-                            return Err(anyhow::anyhow!(
-                                "Read {} bytes, expected {}.",
-                                count,
-                                buffer.len()
-                            ));
-                        }
-                        if let Some(progress) = self.options.progress {
-                            progress.inc(session_options.block_size as u64);
-                        }
-                    }
-                }
-                ReadWrite::Write => {
-                    for _ in 0..ops {
-                        let bytes_written = file.write(buffer)?;
-                        anyhow::ensure!(
-                            bytes_written == buffer.len(),
-                            "Failed to write all bytes to file.",
-                        );
-
-                        if let Some(progress) = self.options.progress {
-                            progress.inc(session_options.block_size as u64);
-                        }
-                    }
-                }
+        // Page faults only tell something about memory mapped I/O.
+        let mapped = mapping.is_some();
+        let count_page_faults = || mapped.then(page_fault_counts).flatten();
+        let page_faults_before = count_page_faults();
+        let (elapsed, tally) = measure(|| -> Result<Tally> {
+            match mapping.as_mut() {
+                Some(mapping) => self.mmap_io(mapping, buffer, workload),
+                None => self.syscall_io(file, buffer, workload),
             }
-            Ok(())
         });
+        let tally = tally?;
+        let page_faults = PageFaults::between(page_faults_before, count_page_faults());
 
         let result = CycleResult {
             cycle: self.options.cycle,
-            bytes: session_options.file_size,
+            bytes: tally.bytes,
             elapsed,
+            ops: tally.ops,
+            page_faults,
+            block_sizes: match session_options.block_sizes {
+                Some(_) => tally.block_sizes.into_values().collect(),
+                None => Vec::new(),
+            },
+            samples: tally.samples,
+            latencies: tally.latencies,
         };
-        log::debug!(target: "Session::Run::Cycle", "Ending cycle.");
         Ok(result)
     }
+
+    /// Performs operations until a cycle's worth of bytes has been transferred. `op` reads or writes one block at
+    /// the given offset.
+    fn perform<F>(&self, buffer: &mut [u8], workload: &mut Workload, mut op: F) -> Result<Tally>
+    where
+        F: FnMut(usize, &mut [u8]) -> Result<()>,
+    {
+        let session_options = &self.options.run_options.session_options;
+        let mut pacer = Pacer::new(session_options.rate_iops, session_options.rate_bytes);
+        let mut sampler = ThroughputSampler::new(
+            session_options.sample_interval,
+            session_options.sample_bytes,
+        );
+        let duration = session_options
+            .sustained
+            .as_ref()
+            .and_then(|s| s.duration)
+            .map(std::time::Duration::from_secs_f64);
+        let start = std::time::Instant::now();
+        let mut tally = Tally::default();
+        let interrupted = self.options.run_options.interrupted;
+        while tally.bytes < session_options.cycle_bytes()
+            && duration.is_none_or(|duration| start.elapsed() < duration)
+            && !interrupted.load(Ordering::Relaxed)
+        {
+            let size = workload.sizes.next();
+            // Stop short of the budget rather than wrap around and transfer the first blocks of the file again.
+            if tally.bytes.saturating_add(size) > session_options.cycle_bytes() {
+                break;
+            }
+            let offset = workload.offsets.next(size);
+            let block = &mut buffer[..size];
+            let due = pacer.as_mut().map(|pacer| pacer.wait(size));
+            let (elapsed, result) = measure(|| op(offset, block));
+            result.located(Some(offset), None)?;
+            let latency = due.map_or(elapsed, |due| due.elapsed().as_secs_f64());
+            tally.record(size, elapsed, latency);
+            if let Some(sampler) = sampler.as_mut() {
+                sampler.record(size);
+            }
+            let run_options = self.options.run_options;
+            run_options
+                .observer
+                .operation(run_options.info, size, latency);
+        }
+        if let Some(sampler) = sampler {
+            tally.samples = sampler.finish();
+        }
+        Ok(tally)
+    }
+
+    fn syscall_io(
+        &self,
+        mut file: &File,
+        buffer: &mut [u8],
+        workload: &mut Workload,
+    ) -> Result<Tally> {
+        let mode = self.options.run_options.mode;
+        let path = &self.options.run_options.session_options.path;
+        let mut position = file.stream_position().file_context(ErrorKind::Io, path)? as usize;
+
+        self.perform(buffer, workload, |offset, block| {
+            if offset != position {
+                file.seek(std::io::SeekFrom::Start(offset as u64))
+                    .file_context(ErrorKind::Io, path)?;
+            }
+            let (verb, count) = match mode {
+                ReadWrite::Read => ("Read", file.read(block).file_context(ErrorKind::Io, path)?),
+                ReadWrite::Write => (
+                    "Wrote",
+                    file.write(block).file_context(ErrorKind::Io, path)?,
+                ),
+            };
+            if count != block.len() {
+                return Err(FileError::with_detail(
+                    ErrorKind::ShortTransfer,
+                    path,
+                    format!("{} {} bytes, expected {}.", verb, count, block.len()),
+                )
+                .into());
+            }
+            position = offset + block.len();
+            Ok(())
+        })
+    }
+
+    fn mmap_io(
+        &self,
+        mapping: &mut MappedFile,
+        buffer: &mut [u8],
+        workload: &mut Workload,
+    ) -> Result<Tally> {
+        let mode = self.options.run_options.mode;
+        let path = &self.options.run_options.session_options.path;
+
+        let tally = self.perform(buffer, workload, |offset, block| {
+            match mode {
+                ReadWrite::Read => mapping.read(offset, block),
+                ReadWrite::Write => mapping.write(offset, block),
+            }
+            .file_context(ErrorKind::Io, path)
+        })?;
+        if mode == &ReadWrite::Write && !self.options.run_options.session_options.no_msync {
+            mapping.sync().file_context(ErrorKind::Io, path)?;
+        }
+        Ok(tally)
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 pub struct RunStatistics {
     pub mean: f64,
     pub median: f64,
     pub standard_deviation: f64,
     pub min: f64,
     pub max: f64,
+    /// Total bytes divided by total elapsed time of all cycles.
+    #[serde(default)]
+    pub total_throughput: f64,
+    /// Total operations divided by total elapsed time of all cycles.
+    #[serde(default)]
+    pub iops: f64,
+    #[serde(default)]
+    pub harmonic_mean: f64,
+    /// Standard deviation divided by the mean.
+    #[serde(default)]
+    pub coefficient_of_variation: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mean_interval: Option<ConfidenceInterval>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub median_interval: Option<ConfidenceInterval>,
+    /// Indices of the cycles whose throughput is an outlier.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outliers: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub block_sizes: Vec<BlockSizeStatistics>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyStatistics>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+pub struct BlockSizeStatistics {
+    pub block_size: usize,
+    pub ops: usize,
+    pub bytes: usize,
+    /// Bytes per second while performing operations of this size.
+    pub throughput: f64,
+    /// Mean duration of an operation of this size in seconds.
+    pub mean_latency: f64,
 }
 
 impl RunStatistics {
@@ -424,9 +1080,44 @@ impl RunStatistics {
             .collect::<Vec<f64>>();
         let mean = statistical::mean(&timings);
         let median = statistical::median(&timings);
-        let standard_deviation = statistical::standard_deviation(&timings, Some(mean));
+        let standard_deviation = if timings.len() > 1 {
+            statistical::standard_deviation(&timings, Some(mean))
+        } else {
+            0.0
+        };
         let min = min(&timings);
         let max = max(&timings);
+        let total_bytes: usize = cycle_results.iter().map(|r| r.bytes).sum();
+        let total_elapsed: f64 = cycle_results.iter().map(|r| r.elapsed).sum();
+        let total_ops: usize = cycle_results.iter().map(|r| r.ops).sum();
+
+        let mut block_sizes: BTreeMap<usize, BlockSizeResult> = BTreeMap::new();
+        for result in cycle_results.iter().flat_map(|c| c.block_sizes.iter()) {
+            let total = block_sizes
+                .entry(result.block_size)
+                .or_insert(BlockSizeResult {
+                    block_size: result.block_size,
+                    ..Default::default()
+                });
+            total.ops += result.ops;
+            total.bytes += result.bytes;
+            total.elapsed += result.elapsed;
+        }
+        let block_sizes = block_sizes
+            .into_values()
+            .map(|total| BlockSizeStatistics {
+                block_size: total.block_size,
+                ops: total.ops,
+                bytes: total.bytes,
+                throughput: total.bytes as f64 / total.elapsed,
+                mean_latency: total.elapsed / total.ops as f64,
+            })
+            .collect();
+
+        let mut latencies = LatencyHistogram::default();
+        for cycle_result in cycle_results {
+            latencies.merge(&cycle_result.latencies);
+        }
 
         RunStatistics {
             mean,
@@ -434,6 +1125,15 @@ impl RunStatistics {
             standard_deviation,
             min,
             max,
+            total_throughput: total_bytes as f64 / total_elapsed,
+            iops: total_ops as f64 / total_elapsed,
+            harmonic_mean: harmonic_mean(&timings),
+            coefficient_of_variation: standard_deviation / mean,
+            mean_interval: ConfidenceInterval::bootstrap(&timings, 0.95, statistical::mean),
+            median_interval: ConfidenceInterval::bootstrap(&timings, 0.95, statistical::median),
+            outliers: outliers(&timings),
+            block_sizes,
+            latency: LatencyStatistics::new(&latencies),
         }
     }
 }
@@ -460,6 +1160,21 @@ mod tests {
                 random_seek: false,
                 no_close_file: false,
                 no_random_buffer: false,
+                compare_cache: false,
+                engine: Engine::Syscall,
+                madvise: None,
+                no_msync: false,
+                distribution: OffsetDistribution::Uniform,
+                pattern: AccessPattern::Sequential,
+                block_sizes: None,
+                rate_iops: None,
+                rate_bytes: None,
+                sample_interval: None,
+                sample_bytes: None,
+                sustained: None,
+                adaptive: None,
+                dashboard: false,
+                stream: None,
             }
         }
     }
@@ -471,8 +1186,273 @@ mod tests {
         let file_path = tmp_dir.path().join("test.dat");
 
         let options = SessionOptions::new(&file_path, 1024 * 1024, 1024, 2);
-        let session = Session { options };
+        let session = Session::new(options);
         let result = session.main().unwrap();
         println!("{}", serde_json::to_string_pretty(&result).unwrap());
     }
+
+    #[test]
+    fn test_compare_cache() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+
+        let mut options = SessionOptions::new(&file_path, 1024 * 1024, 1024, 2);
+        options.compare_cache = true;
+        let session = Session::new(options);
+        let result = session.main().unwrap();
+        let runs: Vec<(ReadWrite, CachePolicy)> = result
+            .runs
+            .iter()
+            .map(|r| (r.mode.clone(), r.cache))
+            .collect();
+        assert_eq!(
+            runs,
+            vec![
+                (ReadWrite::Read, CachePolicy::Direct),
+                (ReadWrite::Read, CachePolicy::Buffered),
+                (ReadWrite::Write, CachePolicy::Direct),
+                (ReadWrite::Write, CachePolicy::Buffered),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mmap_engine() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+
+        let mut options = SessionOptions::new(&file_path, 1024 * 1024, 4096, 2);
+        options.engine = Engine::Mmap;
+        options.madvise = Some(Advice::Sequential);
+        let session = Session::new(options);
+        let result = session.main().unwrap();
+        assert_eq!(result.runs.len(), 2);
+        for run in result.runs {
+            assert!(run.cycle_results.iter().all(|c| c.page_faults.is_some()));
+        }
+
+        let mut options = SessionOptions::new(&file_path, 1024 * 1024, 4096, 2);
+        options.engine = Engine::Mmap;
+        options.no_msync = true;
+        assert_eq!(Session::new(options).main().unwrap().runs.len(), 2);
+
+        let options = SessionOptions::new(&file_path, 1024 * 1024, 4096, 2);
+        let result = Session::new(options).main().unwrap();
+        for run in result.runs {
+            assert!(run.cycle_results.iter().all(|c| c.page_faults.is_none()));
+        }
+    }
+
+    #[test]
+    fn test_random_seek() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+
+        let mut options = SessionOptions::new(&file_path, 1024 * 1024, 4096, 2);
+        options.random_seek = true;
+        options.distribution = OffsetDistribution::Zipf { theta: 0.99 };
+        let session = Session::new(options);
+        let result = session.main().unwrap();
+        assert_eq!(result.runs.len(), 2);
+    }
+
+    #[test]
+    fn test_block_sizes() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+
+        let mut options = SessionOptions::new(&file_path, 1024 * 1024, 64 * 1024, 2);
+        options.block_sizes = Some(parse_block_sizes("4KB:2,64KB:1").unwrap());
+        options.random_seek = true;
+        let session = Session::new(options);
+        let result = session.main().unwrap();
+        for run in result.runs {
+            for cycle in run.cycle_results.iter() {
+                assert!(cycle.bytes <= 1024 * 1024 && cycle.bytes > 1024 * 1024 - 64 * 1024);
+                assert_eq!(
+                    cycle.ops,
+                    cycle.block_sizes.iter().map(|b| b.ops).sum::<usize>()
+                );
+            }
+            let sizes: Vec<usize> = run
+                .statistics
+                .block_sizes
+                .iter()
+                .map(|b| b.block_size)
+                .collect();
+            assert_eq!(sizes, vec![4096, 65536]);
+        }
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+
+        let mut options = SessionOptions::new(&file_path, 64 * 1024, 4096, 2);
+        options.modes = vec![ReadWrite::Read];
+        options.rate_iops = Some(1000.0);
+        let session = Session::new(options);
+        let result = session.main().unwrap();
+        let run = &result.runs[0];
+        // 16 operations at 1000 IOPS can't take less than 15ms.
+        assert!(run.cycle_results.iter().all(|c| c.elapsed >= 0.015));
+        assert!(run.statistics.latency.is_some());
+    }
+
+    #[test]
+    fn test_sampling() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+
+        let mut options = SessionOptions::new(&file_path, 1024 * 1024, 4096, 2);
+        options.sample_bytes = Some(256 * 1024);
+        let session = Session::new(options);
+        let result = session.main().unwrap();
+        for cycle in result.runs.iter().flat_map(|r| r.cycle_results.iter()) {
+            assert_eq!(cycle.samples.len(), 4);
+            assert_eq!(
+                cycle.samples.iter().map(|s| s.bytes).sum::<usize>(),
+                cycle.bytes
+            );
+        }
+    }
+
+    #[test]
+    fn test_sustained() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+
+        let mut options = SessionOptions::new(&file_path, 1024 * 1024, 4096, 1);
+        options.modes = vec![ReadWrite::Write];
+        options.sample_bytes = Some(512 * 1024);
+        options.sustained = Some(SustainedOptions {
+            bytes: Some(4 * 1024 * 1024),
+            duration: Some(60.0),
+        });
+        let session = Session::new(options);
+        let result = session.main().unwrap();
+        let run = &result.runs[0];
+        assert_eq!(run.cycle_results.len(), 1);
+        assert_eq!(run.cycle_results[0].bytes, 4 * 1024 * 1024);
+        assert_eq!(run.cycle_results[0].samples.len(), 8);
+        assert!(run.sustained.is_some());
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+
+        let mut options = SessionOptions::new(&file_path, 256 * 1024, 4096, 1);
+        options.modes = vec![ReadWrite::Read];
+        options.adaptive = Some(AdaptiveOptions {
+            max_cycles: 10,
+            window: 3,
+            // Loose enough that any three cycles converge.
+            target_rse: Some(10.0),
+        });
+        let session = Session::new(options);
+        let result = session.main().unwrap();
+        let run = &result.runs[0];
+        let convergence = run.convergence.as_ref().unwrap();
+        assert!(convergence.converged);
+        assert_eq!(convergence.cycles, 3);
+        assert_eq!(run.cycle_results.len(), 3);
+    }
+
+    /// Interrupts the session once the first run has completed a cycle.
+    struct Interrupter(Arc<AtomicBool>);
+
+    impl Observer for Interrupter {
+        fn cycle_completed(&self, _run: &RunInfo, _cycle: &CycleResult) -> Result<()> {
+            self.0.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+        let mut options = SessionOptions::new(&file_path, 64 * 1024, 4096, 4);
+        options.no_progress = true;
+        options.no_delete = false;
+        let session = Session::new(options.clone());
+        let interrupter = Interrupter(session.interrupt_flag());
+        let result = session.observe(interrupter).main().unwrap();
+        assert!(result.interrupted);
+        assert_eq!(result.runs.len(), 1);
+        assert_eq!(result.runs[0].mode, ReadWrite::Read);
+        assert_eq!(result.runs[0].cycle_results.len(), 1);
+        assert!(!file_path.exists());
+
+        let session = Session::new(options);
+        session.interrupt_flag().store(true, Ordering::Relaxed);
+        let result = session.main().unwrap();
+        assert!(result.interrupted);
+        assert!(result.runs.is_empty());
+        assert!(!file_path.exists());
+    }
+
+    /// Truncates the test file after the first run, so the next one fails.
+    struct Truncator(PathBuf);
+
+    impl Observer for Truncator {
+        fn run_completed(&self, _run: &RunInfo, _result: &RunResult) -> Result<()> {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(&self.0)?
+                .set_len(4096)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_run_failure() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+        let mut options = SessionOptions::new(&file_path, 64 * 1024, 4096, 2);
+        options.no_progress = true;
+        options.compare_cache = true;
+        let session = Session::new(options).observe(Truncator(file_path.clone()));
+        let result = session.main().unwrap();
+        assert_eq!(result.runs.len(), 1);
+        assert_eq!(result.failures.len(), 3);
+        let failure = &result.failures[0];
+        assert_eq!(failure.mode, ReadWrite::Read);
+        assert_eq!(failure.cache, CachePolicy::Buffered);
+        assert_eq!(failure.kind, ErrorKind::Verification);
+        assert!(failure.message.contains("expected at least 65536"));
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["failures"][0]["kind"], "Verification");
+    }
+
+    #[test]
+    fn test_cleanup() {
+        let tmp_dir = tempdir().unwrap();
+        let file_path = tmp_dir.path().join("test.dat");
+        let mut options = SessionOptions::new(&file_path, 64 * 1024, 4096, 2);
+        options.no_progress = true;
+        options.no_delete = false;
+
+        // The stream is opened before the test file is created.
+        options.stream = Some(StreamOptions {
+            path: tmp_dir.path().join("missing").join("stream.jsonl"),
+            format: StreamFormat::Jsonl,
+        });
+        assert!(Session::new(options.clone()).main().is_err());
+        assert!(!file_path.exists());
+
+        // A session that fails after creating the test file deletes it, unless told to keep it.
+        std::fs::write(&file_path, b"test").unwrap();
+        drop(TestFile::new(&options));
+        assert!(!file_path.exists());
+
+        options.no_delete = true;
+        std::fs::write(&file_path, b"test").unwrap();
+        drop(TestFile::new(&options));
+        assert!(file_path.exists());
+    }
 }
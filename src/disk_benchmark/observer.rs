@@ -0,0 +1,285 @@
+use anyhow::Result;
+
+use super::{CachePolicy, CycleResult, ReadWrite, RunResult, SessionOptions, SessionResult};
+use crate::{error::FileError, support::DataSize, Error};
+
+/// A run of a session: one mode with one cache policy.
+#[derive(Debug, Clone)]
+pub struct RunInfo {
+    /// Position of the run in the session.
+    pub index: usize,
+    pub mode: ReadWrite,
+    pub cache: CachePolicy,
+    /// The number of cycles the run performs at most.
+    pub cycles: usize,
+    /// The number of bytes the run transfers at most, if known.
+    pub total_bytes: Option<u64>,
+}
+
+/// Receives events while a session runs, e.g. to show progress or forward results to telemetry. Add observers with
+/// [`Session::observe`](super::Session::observe), all methods do nothing by default.
+///
+/// Methods return a `Result` so implementations can use `?`, but an error is only logged: the session goes on, so
+/// a failing observer can't end a long benchmark. Set the session's
+/// [`interrupt_flag`](super::Session::interrupt_flag) to stop it instead.
+pub trait Observer {
+    /// The test file is ready and `runs` are about to be performed in order.
+    fn session_started(&self, _options: &SessionOptions, _runs: &[RunInfo]) -> Result<()> {
+        Ok(())
+    }
+
+    fn run_started(&self, _run: &RunInfo) -> Result<()> {
+        Ok(())
+    }
+
+    fn cycle_started(&self, _run: &RunInfo, _cycle: usize) -> Result<()> {
+        Ok(())
+    }
+
+    /// An operation of `size` bytes completed, `latency` is how long it took in seconds. Called for every read or
+    /// write, so keep it cheap.
+    fn operation(&self, _run: &RunInfo, _size: usize, _latency: f64) {}
+
+    fn cycle_completed(&self, _run: &RunInfo, _cycle: &CycleResult) -> Result<()> {
+        Ok(())
+    }
+
+    fn run_completed(&self, _run: &RunInfo, _result: &RunResult) -> Result<()> {
+        Ok(())
+    }
+
+    /// The run failed, the session goes on with the next one.
+    fn run_failed(&self, _run: &RunInfo, _error: &FileError) -> Result<()> {
+        Ok(())
+    }
+
+    fn session_completed(&self, _result: &SessionResult) -> Result<()> {
+        Ok(())
+    }
+
+    /// The session failed, no further events follow.
+    fn error(&self, _error: &Error) {}
+}
+
+/// Passes every event on to each of its observers, in order. Errors are logged, the event still reaches the other
+/// observers, so none of these methods can fail.
+pub struct Observers<'a>(pub Vec<&'a dyn Observer>);
+
+impl Observers<'_> {
+    fn each(&self, event: impl Fn(&dyn Observer) -> Result<()>) {
+        for observer in self.0.iter() {
+            if let Err(error) = event(*observer) {
+                log::error!("Observer failed: {:#}", error);
+            }
+        }
+    }
+
+    pub fn session_started(&self, options: &SessionOptions, runs: &[RunInfo]) {
+        self.each(|o| o.session_started(options, runs))
+    }
+
+    pub fn run_started(&self, run: &RunInfo) {
+        self.each(|o| o.run_started(run))
+    }
+
+    pub fn cycle_started(&self, run: &RunInfo, cycle: usize) {
+        self.each(|o| o.cycle_started(run, cycle))
+    }
+
+    pub fn operation(&self, run: &RunInfo, size: usize, latency: f64) {
+        for observer in self.0.iter() {
+            observer.operation(run, size, latency);
+        }
+    }
+
+    pub fn cycle_completed(&self, run: &RunInfo, cycle: &CycleResult) {
+        self.each(|o| o.cycle_completed(run, cycle))
+    }
+
+    pub fn run_completed(&self, run: &RunInfo, result: &RunResult) {
+        self.each(|o| o.run_completed(run, result))
+    }
+
+    pub fn run_failed(&self, run: &RunInfo, error: &FileError) {
+        self.each(|o| o.run_failed(run, error))
+    }
+
+    pub fn session_completed(&self, result: &SessionResult) {
+        self.each(|o| o.session_completed(result))
+    }
+
+    pub fn error(&self, error: &Error) {
+        for observer in self.0.iter() {
+            observer.error(error);
+        }
+    }
+}
+
+impl std::fmt::Debug for Observers<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+/// Logs the progress of a session at debug level.
+#[derive(Debug)]
+pub struct Logger;
+
+impl Observer for Logger {
+    fn session_started(&self, options: &SessionOptions, runs: &[RunInfo]) -> Result<()> {
+        log::debug!(target: "Session", "Starting {} runs on {}.", runs.len(), options.path.display());
+        Ok(())
+    }
+
+    fn run_started(&self, run: &RunInfo) -> Result<()> {
+        log::debug!(target: "Session::Run", "Starting run {} ({}).", run.mode, run.cache);
+        Ok(())
+    }
+
+    fn cycle_started(&self, run: &RunInfo, cycle: usize) -> Result<()> {
+        log::debug!(target: "Session::Run::Cycle", "Starting cycle {}/{}.", cycle + 1, run.cycles);
+        Ok(())
+    }
+
+    fn cycle_completed(&self, _run: &RunInfo, cycle: &CycleResult) -> Result<()> {
+        log::debug!(target: "Session::Run::Cycle", "Ending cycle, {} in {:.3}s.", DataSize::from(cycle.bytes).to_human_string(), cycle.elapsed);
+        Ok(())
+    }
+
+    fn run_completed(&self, _run: &RunInfo, result: &RunResult) -> Result<()> {
+        log::debug!(target: "Session::Run", "Ending run, {} cycles.", result.cycle_results.len());
+        Ok(())
+    }
+
+    fn run_failed(&self, run: &RunInfo, error: &FileError) -> Result<()> {
+        log::error!("{} ({}) run failed: {}", run.mode, run.cache, error);
+        Ok(())
+    }
+
+    fn error(&self, error: &Error) {
+        log::debug!(target: "Session", "Session failed: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disk_benchmark::Session;
+    use std::{cell::RefCell, rc::Rc};
+    use tempfile::tempdir;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Rc<RefCell<Vec<String>>>,
+        operations: Rc<RefCell<usize>>,
+    }
+
+    impl Observer for Recorder {
+        fn session_started(&self, _options: &SessionOptions, runs: &[RunInfo]) -> Result<()> {
+            self.events
+                .borrow_mut()
+                .push(format!("session {}", runs.len()));
+            Ok(())
+        }
+
+        fn run_started(&self, run: &RunInfo) -> Result<()> {
+            self.events
+                .borrow_mut()
+                .push(format!("run {} {}", run.index, run.mode));
+            Ok(())
+        }
+
+        fn cycle_started(&self, _run: &RunInfo, cycle: usize) -> Result<()> {
+            self.events.borrow_mut().push(format!("cycle {}", cycle));
+            Ok(())
+        }
+
+        fn operation(&self, _run: &RunInfo, _size: usize, _latency: f64) {
+            *self.operations.borrow_mut() += 1;
+        }
+
+        fn cycle_completed(&self, _run: &RunInfo, cycle: &CycleResult) -> Result<()> {
+            self.events
+                .borrow_mut()
+                .push(format!("cycle {} done", cycle.cycle));
+            Ok(())
+        }
+
+        fn run_completed(&self, run: &RunInfo, _result: &RunResult) -> Result<()> {
+            self.events
+                .borrow_mut()
+                .push(format!("run {} done", run.index));
+            Ok(())
+        }
+
+        fn session_completed(&self, result: &SessionResult) -> Result<()> {
+            self.events
+                .borrow_mut()
+                .push(format!("session done {}", result.runs.len()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_observer() {
+        let tmp_dir = tempdir().unwrap();
+        let options = SessionOptions::builder(tmp_dir.path().join("test.dat"))
+            .file_size(64 * 1024)
+            .block_size(4096)
+            .cycles(2)
+            .build()
+            .unwrap();
+        let recorder = Recorder::default();
+        let (events, operations) = (recorder.events.clone(), recorder.operations.clone());
+        Session::new(options).observe(recorder).main().unwrap();
+
+        assert_eq!(
+            *events.borrow(),
+            [
+                "session 2",
+                "run 0 Read",
+                "cycle 0",
+                "cycle 0 done",
+                "cycle 1",
+                "cycle 1 done",
+                "run 0 done",
+                "run 1 Write",
+                "cycle 0",
+                "cycle 0 done",
+                "cycle 1",
+                "cycle 1 done",
+                "run 1 done",
+                "session done 2",
+            ]
+        );
+        assert_eq!(*operations.borrow(), 2 * 2 * 16);
+    }
+
+    struct Failing;
+
+    impl Observer for Failing {
+        fn cycle_completed(&self, _run: &RunInfo, _cycle: &CycleResult) -> Result<()> {
+            anyhow::bail!("Telemetry unavailable.")
+        }
+    }
+
+    #[test]
+    fn test_observer_error() {
+        let tmp_dir = tempdir().unwrap();
+        let options = SessionOptions::builder(tmp_dir.path().join("test.dat"))
+            .file_size(64 * 1024)
+            .block_size(4096)
+            .cycles(2)
+            .build()
+            .unwrap();
+        let recorder = Recorder::default();
+        let events = recorder.events.clone();
+        let result = Session::new(options)
+            .observe(Failing)
+            .observe(recorder)
+            .main()
+            .unwrap();
+        assert_eq!(result.runs.len(), 2);
+        assert_eq!(events.borrow().last().unwrap(), "session done 2");
+    }
+}
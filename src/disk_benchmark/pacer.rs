@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+/// Schedules operations so a cycle does not exceed an IOPS and/or bandwidth limit.
+///
+/// Operation `n` is due once the operations and bytes before it fit within the limits (a token bucket with no
+/// burst). The schedule never slips: if an operation is late the next ones are already due, so latency measured from
+/// the due time includes the time spent waiting behind slow operations (avoiding coordinated omission).
+#[derive(Debug)]
+pub struct Pacer {
+    iops: Option<f64>,
+    bytes_per_second: Option<f64>,
+    start: Instant,
+    ops: u64,
+    bytes: u64,
+}
+
+impl Pacer {
+    pub fn new(iops: Option<f64>, bytes_per_second: Option<usize>) -> Option<Self> {
+        if iops.is_none() && bytes_per_second.is_none() {
+            return None;
+        }
+        Some(Pacer {
+            iops,
+            bytes_per_second: bytes_per_second.map(|b| b as f64),
+            start: Instant::now(),
+            ops: 0,
+            bytes: 0,
+        })
+    }
+
+    /// Returns when the next operation of `size` bytes is due.
+    pub fn schedule(&mut self, size: usize) -> Instant {
+        let by_ops = self.iops.map_or(0.0, |iops| self.ops as f64 / iops);
+        let by_bytes = self
+            .bytes_per_second
+            .map_or(0.0, |rate| self.bytes as f64 / rate);
+        self.ops += 1;
+        self.bytes += size as u64;
+        self.start + Duration::from_secs_f64(by_ops.max(by_bytes))
+    }
+
+    /// Waits until the next operation of `size` bytes is due and returns its due time.
+    pub fn wait(&mut self, size: usize) -> Instant {
+        let due = self.schedule(size);
+        // Sleep for the bulk of the wait, then spin, sleep granularity is too coarse for high IOPS.
+        const SPIN: Duration = Duration::from_micros(200);
+        let now = Instant::now();
+        if due > now + SPIN {
+            std::thread::sleep(due - now - SPIN);
+        }
+        while Instant::now() < due {
+            std::hint::spin_loop();
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule() {
+        assert!(Pacer::new(None, None).is_none());
+
+        let mut pacer = Pacer::new(Some(100.0), None).unwrap();
+        let start = pacer.start;
+        let due: Vec<Duration> = (0..3).map(|_| pacer.schedule(4096) - start).collect();
+        assert_eq!(
+            due,
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(10),
+                Duration::from_millis(20)
+            ]
+        );
+
+        // The tighter of the two limits wins.
+        let mut pacer = Pacer::new(Some(1000.0), Some(1024)).unwrap();
+        let start = pacer.start;
+        pacer.schedule(512);
+        assert_eq!(pacer.schedule(512) - start, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_wait() {
+        let start = Instant::now();
+        let mut pacer = Pacer::new(Some(1000.0), None).unwrap();
+        for _ in 0..11 {
+            pacer.wait(1);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+}
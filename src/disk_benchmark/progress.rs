@@ -0,0 +1,94 @@
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::cell::RefCell;
+
+use super::{FileError, Observer, RunInfo, RunResult, SessionResult};
+use crate::Error;
+
+/// Shows a progress bar on stderr for each run, or a spinner if a run's size isn't known.
+#[derive(Debug)]
+pub struct Progress {
+    /// Include the cache policy in the bar's prefix.
+    show_cache: bool,
+    bar: RefCell<Option<ProgressBar>>,
+}
+
+impl Progress {
+    pub fn new(show_cache: bool) -> Self {
+        Progress {
+            show_cache,
+            bar: RefCell::new(None),
+        }
+    }
+}
+
+impl Observer for Progress {
+    fn run_started(&self, run: &RunInfo) -> Result<()> {
+        let bar = match run.total_bytes {
+            None => {
+                let p = ProgressBar::new_spinner();
+                p.set_style(ProgressStyle::with_template(
+                    "{prefix:5.green} {spinner} {elapsed_precise} {bytes:9} {bytes_per_sec} {msg}",
+                )?);
+                p
+            }
+            Some(total_bytes) => {
+                let p = ProgressBar::new(total_bytes);
+                p.set_style(ProgressStyle::with_template("{prefix:5.green} {spinner} {elapsed_precise} / {eta_precise} {bar:50.green/white} {bytes:9} {msg}")?
+                .progress_chars("#-"),
+                );
+                p
+            }
+        };
+        if self.show_cache {
+            bar.set_prefix(format!("{} ({})", run.mode, run.cache));
+        } else {
+            bar.set_prefix(format!("{}", run.mode));
+        }
+        *self.bar.borrow_mut() = Some(bar);
+        Ok(())
+    }
+
+    fn cycle_started(&self, _run: &RunInfo, _cycle: usize) -> Result<()> {
+        if let Some(bar) = self.bar.borrow().as_ref() {
+            bar.inc(0);
+        }
+        Ok(())
+    }
+
+    fn operation(&self, _run: &RunInfo, size: usize, _latency: f64) {
+        if let Some(bar) = self.bar.borrow().as_ref() {
+            bar.inc(size as u64);
+        }
+    }
+
+    fn run_completed(&self, _run: &RunInfo, _result: &RunResult) -> Result<()> {
+        if let Some(bar) = self.bar.borrow_mut().take() {
+            // Adaptive runs can stop before the progress bar is full.
+            bar.set_length(bar.position());
+            bar.finish();
+        }
+        Ok(())
+    }
+
+    fn run_failed(&self, _run: &RunInfo, _error: &FileError) -> Result<()> {
+        if let Some(bar) = self.bar.borrow_mut().take() {
+            bar.abandon_with_message("failed");
+        }
+        Ok(())
+    }
+
+    fn session_completed(&self, _result: &SessionResult) -> Result<()> {
+        // A run interrupted before its first cycle completed is discarded without a run_completed event.
+        if let Some(bar) = self.bar.borrow_mut().take() {
+            bar.abandon_with_message("interrupted");
+        }
+        Ok(())
+    }
+
+    fn error(&self, _error: &Error) {
+        if let Some(bar) = self.bar.borrow_mut().take() {
+            bar.abandon_with_message("failed");
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Bytes and operations completed during one sampling interval of a cycle.
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
+pub struct ThroughputSample {
+    /// Seconds since the start of the cycle at the end of the interval.
+    pub time: f64,
+    /// Length of the interval in seconds.
+    pub duration: f64,
+    pub bytes: usize,
+    pub ops: usize,
+}
+
+impl ThroughputSample {
+    /// Bytes per second during the interval.
+    pub fn throughput(&self) -> f64 {
+        self.bytes as f64 / self.duration
+    }
+}
+
+/// Cuts a cycle into samples every `interval` and/or every `bytes` transferred, whichever comes first.
+#[derive(Debug)]
+pub struct ThroughputSampler {
+    interval: Option<Duration>,
+    bytes: Option<usize>,
+    start: Instant,
+    last: Instant,
+    current_bytes: usize,
+    current_ops: usize,
+    samples: Vec<ThroughputSample>,
+}
+
+impl ThroughputSampler {
+    pub fn new(interval: Option<f64>, bytes: Option<usize>) -> Option<Self> {
+        if interval.is_none() && bytes.is_none() {
+            return None;
+        }
+        let now = Instant::now();
+        Some(ThroughputSampler {
+            interval: interval.map(Duration::from_secs_f64),
+            bytes,
+            start: now,
+            last: now,
+            current_bytes: 0,
+            current_ops: 0,
+            samples: Vec::new(),
+        })
+    }
+
+    /// Records a completed operation of `size` bytes.
+    pub fn record(&mut self, size: usize) {
+        self.current_bytes += size;
+        self.current_ops += 1;
+        let now = Instant::now();
+        let interval_elapsed = self.interval.is_some_and(|i| now - self.last >= i);
+        let bytes_reached = self.bytes.is_some_and(|b| self.current_bytes >= b);
+        if interval_elapsed || bytes_reached {
+            self.sample(now);
+        }
+    }
+
+    /// Returns the samples, including a final partial one for any operations since the last sample.
+    pub fn finish(mut self) -> Vec<ThroughputSample> {
+        if self.current_ops > 0 {
+            self.sample(Instant::now());
+        }
+        self.samples
+    }
+
+    fn sample(&mut self, now: Instant) {
+        self.samples.push(ThroughputSample {
+            time: (now - self.start).as_secs_f64(),
+            duration: (now - self.last).as_secs_f64(),
+            bytes: self.current_bytes,
+            ops: self.current_ops,
+        });
+        self.last = now;
+        self.current_bytes = 0;
+        self.current_ops = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_by_bytes() {
+        assert!(ThroughputSampler::new(None, None).is_none());
+
+        let mut sampler = ThroughputSampler::new(None, Some(1000)).unwrap();
+        for _ in 0..25 {
+            sampler.record(100);
+        }
+        let samples = sampler.finish();
+        assert_eq!(
+            samples.iter().map(|s| s.bytes).collect::<Vec<_>>(),
+            vec![1000, 1000, 500]
+        );
+        assert_eq!(samples.iter().map(|s| s.ops).sum::<usize>(), 25);
+        assert!(samples.windows(2).all(|w| w[0].time <= w[1].time));
+    }
+
+    #[test]
+    fn test_sample_by_interval() {
+        let mut sampler = ThroughputSampler::new(Some(0.005), None).unwrap();
+        sampler.record(1);
+        std::thread::sleep(Duration::from_millis(10));
+        sampler.record(1);
+        let samples = sampler.finish();
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].ops, 2);
+        assert!(samples[0].duration >= 0.005);
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{anyhow, ensure, Context, Result};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use super::SessionResult;
+use crate::volume::*;
+
+/// Version of the report format written by `--export-json`. Bump it, and add an upgrade to `UPGRADES`, whenever a
+/// change to `SessionResult` means older reports no longer deserialize.
+pub const SCHEMA_VERSION: u32 = 1;
+
+type Upgrade = fn(&mut Map<String, Value>) -> Result<()>;
+
+/// Each upgrade turns a report of the version at its index into one of the next version.
+const UPGRADES: [Upgrade; SCHEMA_VERSION as usize] = [upgrade_v0];
+
+/// The JSON Schema of a report: an array of sessions.
+pub fn report_schema() -> Value {
+    let mut schema = schemars::schema_for!(Vec<SessionResult>);
+    schema.insert("title".to_string(), "simple-disk-benchmark report".into());
+    schema.insert(
+        "description".to_string(),
+        format!("Report format version {}.", SCHEMA_VERSION).into(),
+    );
+    schema.to_value()
+}
+
+/// Reads the sessions of the report at `path`, upgraded to the current version. Kept as JSON values, so nothing is
+/// lost when a report is rewritten, e.g. volume details recorded on another operating system.
+pub fn read_reports(path: &Path) -> Result<Vec<Value>> {
+    let file = std::fs::File::open(path)?;
+    let value: Value = serde_json::from_reader(std::io::BufReader::new(file))
+        .with_context(|| format!("Failed to read report {}", path.display()))?;
+    let sessions = match value {
+        Value::Array(sessions) => sessions,
+        session => vec![session],
+    };
+    sessions
+        .into_iter()
+        .map(upgrade_report)
+        .collect::<Result<_>>()
+        .with_context(|| format!("Failed to upgrade report {}", path.display()))
+}
+
+/// Upgrades a session to the current version. Sessions written before reports were versioned are version 0.
+pub fn upgrade_report(mut session: Value) -> Result<Value> {
+    let object = session
+        .as_object_mut()
+        .ok_or(anyhow!("Session is not an object"))?;
+    let version = match object.get("schema_version") {
+        Some(version) => version
+            .as_u64()
+            .ok_or(anyhow!("Invalid schema version {}", version))?,
+        None => 0,
+    };
+    ensure!(
+        version <= SCHEMA_VERSION as u64,
+        "Schema version {} is newer than this version of simple-disk-benchmark supports ({}).",
+        version,
+        SCHEMA_VERSION
+    );
+    for upgrade in UPGRADES[version as usize..].iter() {
+        upgrade(object)?;
+    }
+    object.insert("schema_version".to_string(), SCHEMA_VERSION.into());
+    Ok(session)
+}
+
+/// Version 0 stored `created` as an RFC 3339 string with a time zone offset and lacked some options.
+fn upgrade_v0(session: &mut Map<String, Value>) -> Result<()> {
+    if let Some(Value::String(created)) = session.get("created") {
+        let created = parse_timestamp(created)?;
+        session.insert("created".to_string(), serde_json::to_value(created)?);
+    }
+    if let Some(Value::Object(options)) = session.get_mut("options") {
+        for option in ["random_seek", "no_close_file", "no_random_buffer"] {
+            options.entry(option).or_insert(Value::Bool(false));
+        }
+    }
+    Ok(())
+}
+
+/// Parses an RFC 3339 timestamp, humantime only handles UTC so the offset is applied here.
+fn parse_timestamp(timestamp: &str) -> Result<SystemTime> {
+    let re = regex::Regex::new(r"^(.+)([+-])(\d{2}):(\d{2})$").expect("Invalid regex");
+    let Some(caps) = re.captures(timestamp) else {
+        return Ok(humantime::parse_rfc3339_weak(timestamp)?);
+    };
+    let local = humantime::parse_rfc3339_weak(&caps[1])?;
+    let offset = Duration::from_secs(caps[3].parse::<u64>()? * 3600 + caps[4].parse::<u64>()? * 60);
+    Ok(if &caps[2] == "+" {
+        local - offset
+    } else {
+        local + offset
+    })
+}
+
+/// Volume details depend on the operating system, ignore those recorded on another one.
+pub(super) fn deserialize_volume<'de, D>(deserializer: D) -> Result<Option<Volume>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|v| serde_json::from_value(v).ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disk_benchmark::{Session, SessionOptions};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_upgrade_v0() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("report.json");
+        std::fs::write(&path, include_str!("testdata/report-v0.json")).unwrap();
+
+        let reports = read_reports(&path).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0]["schema_version"], SCHEMA_VERSION);
+        let session: SessionResult = serde_json::from_value(reports[0].clone()).unwrap();
+        assert_eq!(
+            humantime::format_rfc3339_micros(session.created).to_string(),
+            "2023-08-01T05:11:28.313954Z"
+        );
+        assert_eq!(session.runs.len(), 2);
+        assert!(!session.options.random_seek);
+        // Unknown to this version, but kept.
+        assert_eq!(reports[0]["volume"]["bsd_name"], "disk3s5");
+    }
+
+    #[test]
+    fn test_current_version() {
+        let tmp_dir = tempdir().unwrap();
+        let mut options = SessionOptions::new(&tmp_dir.path().join("test.dat"), 64 * 1024, 4096, 2);
+        options.no_progress = true;
+        let session = Session::new(options).main().unwrap();
+        let value = serde_json::to_value(&session).unwrap();
+        assert_eq!(upgrade_report(value.clone()).unwrap(), value);
+
+        let mut newer = value;
+        newer["schema_version"] = (SCHEMA_VERSION + 1).into();
+        assert!(upgrade_report(newer).is_err());
+    }
+
+    #[test]
+    fn test_schema_is_up_to_date() {
+        let schema = serde_json::to_string_pretty(&report_schema()).unwrap() + "\n";
+        assert_eq!(
+            schema,
+            include_str!("../../docs/report.schema.json"),
+            "docs/report.schema.json is out of date, update it with `simple-disk-benchmark schema > docs/report.schema.json`."
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(
+            parse_timestamp("2023-07-31T22:11:28-07:00").unwrap(),
+            parse_timestamp("2023-08-01T05:11:28Z").unwrap()
+        );
+        assert_eq!(
+            parse_timestamp("2023-08-01T07:11:28+02:00").unwrap(),
+            parse_timestamp("2023-08-01T05:11:28Z").unwrap()
+        );
+    }
+}
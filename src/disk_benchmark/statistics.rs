@@ -0,0 +1,108 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Number of resamples drawn to estimate a bootstrap confidence interval.
+const BOOTSTRAP_RESAMPLES: usize = 10_000;
+
+/// A range that contains the true value of a statistic with the given confidence.
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
+pub struct ConfidenceInterval {
+    pub lower: f64,
+    pub upper: f64,
+    /// e.g. 0.95 for a 95% confidence interval.
+    pub confidence: f64,
+}
+
+impl ConfidenceInterval {
+    /// Percentile bootstrap confidence interval of `statistic` over `values`. Returns `None` for fewer than two
+    /// values. The resampling is seeded so the same values always produce the same interval.
+    pub fn bootstrap<F>(values: &[f64], confidence: f64, statistic: F) -> Option<Self>
+    where
+        F: Fn(&[f64]) -> f64,
+    {
+        if values.len() < 2 {
+            return None;
+        }
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut resample = vec![0.0; values.len()];
+        let mut estimates: Vec<f64> = (0..BOOTSTRAP_RESAMPLES)
+            .map(|_| {
+                for value in resample.iter_mut() {
+                    *value = values[rng.random_range(0..values.len())];
+                }
+                statistic(&resample)
+            })
+            .collect();
+        estimates.sort_by(f64::total_cmp);
+        let alpha = 1.0 - confidence;
+        Some(ConfidenceInterval {
+            lower: quantile(&estimates, alpha / 2.0),
+            upper: quantile(&estimates, 1.0 - alpha / 2.0),
+            confidence,
+        })
+    }
+}
+
+/// The harmonic mean, the correct average of rates measured over equal amounts of work.
+pub fn harmonic_mean(values: &[f64]) -> f64 {
+    values.len() as f64 / values.iter().map(|v| 1.0 / v).sum::<f64>()
+}
+
+/// Indices of the values outside `[Q1 - 1.5 IQR, Q3 + 1.5 IQR]` (Tukey's fences). Needs at least four values.
+pub fn outliers(values: &[f64]) -> Vec<usize> {
+    if values.len() < 4 {
+        return Vec::new();
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let q1 = quantile(&sorted, 0.25);
+    let q3 = quantile(&sorted, 0.75);
+    let iqr = q3 - q1;
+    let (low, high) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+    values
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| **v < low || **v > high)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Quantile of already sorted values, interpolating linearly between the closest ranks.
+fn quantile(sorted: &[f64], fraction: f64) -> f64 {
+    let position = fraction * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_harmonic_mean() {
+        // 1 byte at 1 B/s and 1 byte at 3 B/s takes 4/3 s, i.e. 1.5 B/s.
+        assert_eq!(harmonic_mean(&[1.0, 3.0]), 1.5);
+    }
+
+    #[test]
+    fn test_outliers() {
+        assert_eq!(outliers(&[100.0, 101.0, 99.0, 100.0, 40.0, 102.0]), vec![4]);
+        assert!(outliers(&[100.0, 101.0, 99.0, 100.0]).is_empty());
+        assert!(outliers(&[1.0, 100.0]).is_empty());
+    }
+
+    #[test]
+    fn test_bootstrap() {
+        let values: Vec<f64> = (1..=20).map(|v| v as f64).collect();
+        let interval = ConfidenceInterval::bootstrap(&values, 0.95, statistical::mean).unwrap();
+        assert!(interval.lower < 10.5 && 10.5 < interval.upper);
+        assert!(interval.lower > 7.0 && interval.upper < 14.0);
+        assert_eq!(
+            ConfidenceInterval::bootstrap(&values, 0.95, statistical::mean),
+            Some(interval)
+        );
+        assert!(ConfidenceInterval::bootstrap(&[1.0], 0.95, statistical::mean).is_none());
+    }
+}
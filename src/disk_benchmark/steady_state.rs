@@ -0,0 +1,117 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Keep running cycles until the throughput is steady (or precise enough), up to `max_cycles`.
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
+pub struct AdaptiveOptions {
+    pub max_cycles: usize,
+    /// Number of most recent cycles the steady state criterion is evaluated over.
+    pub window: usize,
+    /// Also stop once the relative standard error of the mean throughput is at or below this fraction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_rse: Option<f64>,
+}
+
+/// How an adaptive run ended.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct Convergence {
+    pub cycles: usize,
+    pub steady_state: bool,
+    /// Standard error of the mean throughput divided by the mean.
+    pub relative_standard_error: f64,
+    pub converged: bool,
+}
+
+// Steady state criteria from the SNIA Solid State Storage Performance Test Specification: within the measurement
+// window, the data excursion (max - min) and the slope excursion of the least squares line must stay within 20% and
+// 10% of the window's average.
+const MAX_RANGE_EXCURSION: f64 = 0.2;
+const MAX_SLOPE_EXCURSION: f64 = 0.1;
+
+impl Convergence {
+    /// Evaluates the throughput of each cycle so far.
+    pub fn new(throughputs: &[f64], options: &AdaptiveOptions) -> Self {
+        let window = options.window.max(2);
+        let steady_state = throughputs.len() >= window
+            && is_steady_state(&throughputs[throughputs.len() - window..]);
+        let relative_standard_error = relative_standard_error(throughputs);
+        let converged = throughputs.len() >= window
+            && (steady_state
+                || options
+                    .target_rse
+                    .is_some_and(|target| relative_standard_error <= target));
+        Convergence {
+            cycles: throughputs.len(),
+            steady_state,
+            relative_standard_error,
+            converged,
+        }
+    }
+}
+
+pub fn is_steady_state(window: &[f64]) -> bool {
+    let n = window.len() as f64;
+    let average = window.iter().sum::<f64>() / n;
+    let max = window.iter().cloned().fold(f64::MIN, f64::max);
+    let min = window.iter().cloned().fold(f64::MAX, f64::min);
+    if max - min > MAX_RANGE_EXCURSION * average {
+        return false;
+    }
+    let mean_x = (n - 1.0) / 2.0;
+    let (covariance, variance) =
+        window
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(covariance, variance), (x, y)| {
+                let dx = x as f64 - mean_x;
+                (covariance + dx * (y - average), variance + dx * dx)
+            });
+    let slope = covariance / variance;
+    (slope * (n - 1.0)).abs() <= MAX_SLOPE_EXCURSION * average
+}
+
+pub fn relative_standard_error(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return f64::INFINITY;
+    }
+    let mean = statistical::mean(values);
+    let standard_deviation = statistical::standard_deviation(values, Some(mean));
+    standard_deviation / (values.len() as f64).sqrt() / mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_steady_state() {
+        assert!(is_steady_state(&[100.0, 102.0, 98.0, 101.0, 99.0]));
+        // Range excursion too large.
+        assert!(!is_steady_state(&[100.0, 130.0, 98.0, 101.0, 99.0]));
+        // Trending up, each value within range but the slope is too steep.
+        assert!(!is_steady_state(&[90.0, 94.0, 98.0, 102.0, 106.0]));
+    }
+
+    #[test]
+    fn test_convergence() {
+        let options = AdaptiveOptions {
+            max_cycles: 100,
+            window: 3,
+            target_rse: None,
+        };
+        assert!(!Convergence::new(&[100.0, 100.0], &options).converged);
+        let convergence = Convergence::new(&[50.0, 100.0, 100.0, 101.0], &options);
+        assert!(convergence.converged);
+        assert!(convergence.steady_state);
+        assert_eq!(convergence.cycles, 4);
+
+        let options = AdaptiveOptions {
+            target_rse: Some(0.5),
+            ..options
+        };
+        let convergence = Convergence::new(&[50.0, 100.0, 150.0], &options);
+        assert!(!convergence.steady_state);
+        assert!(convergence.converged);
+        assert!(relative_standard_error(&[1.0]).is_infinite());
+    }
+}
@@ -0,0 +1,343 @@
+use anyhow::Result;
+use enum_display_derive::Display;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Write as _},
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    CachePolicy, CycleResult, LatencyStatistics, Observer, ReadWrite, RunInfo, RunResult,
+    RunStatistics,
+};
+
+/// Record format of a result stream.
+#[derive(Display, PartialEq, Debug, Clone, Copy, Default, Deserialize, JsonSchema, Serialize)]
+pub enum StreamFormat {
+    /// One JSON object per line.
+    #[default]
+    Jsonl,
+    /// InfluxDB line protocol.
+    Influx,
+}
+
+/// Where and how to stream results.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct StreamOptions {
+    /// File to append records to, `-` for stdout.
+    pub path: PathBuf,
+    pub format: StreamFormat,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Record<'a> {
+    Cycle {
+        #[serde(flatten)]
+        tags: &'a Tags,
+        time: f64,
+        cycle: usize,
+        bytes: usize,
+        ops: usize,
+        elapsed: f64,
+        throughput: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        latency: Option<LatencyStatistics>,
+    },
+    Run {
+        #[serde(flatten)]
+        tags: &'a Tags,
+        time: f64,
+        cycles: usize,
+        statistics: &'a RunStatistics,
+    },
+}
+
+/// What a record describes.
+#[derive(Debug, Serialize)]
+struct Tags {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<String>,
+    path: String,
+    mode: ReadWrite,
+    cache: CachePolicy,
+}
+
+/// Writes a record for every completed cycle and run as they happen, so results can be followed while a benchmark is
+/// still running. Every record is flushed as soon as it is written.
+pub struct ResultStream {
+    writer: Mutex<Box<dyn Write + Send>>,
+    format: StreamFormat,
+    host: Option<String>,
+    path: String,
+}
+
+impl std::fmt::Debug for ResultStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResultStream")
+            .field("format", &self.format)
+            .field("host", &self.host)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResultStream {
+    /// Opens the stream described by `options`, appending to its file if it exists. `path` is the benchmark's test
+    /// file.
+    pub fn open(options: &StreamOptions, path: &Path) -> Result<Self> {
+        let writer: Box<dyn Write + Send> = if options.path == Path::new("-") {
+            Box::new(std::io::stdout())
+        } else {
+            Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&options.path)?,
+            )
+        };
+        Ok(Self::new(writer, options.format, path))
+    }
+
+    fn new(writer: Box<dyn Write + Send>, format: StreamFormat, path: &Path) -> Self {
+        ResultStream {
+            writer: Mutex::new(writer),
+            format,
+            host: super::support::hostname(),
+            path: path.display().to_string(),
+        }
+    }
+
+    fn tags(&self, mode: &ReadWrite, cache: CachePolicy) -> Tags {
+        Tags {
+            host: self.host.clone(),
+            path: self.path.clone(),
+            mode: mode.clone(),
+            cache,
+        }
+    }
+
+    fn write(&self, record: Record) -> Result<()> {
+        let line = match self.format {
+            StreamFormat::Jsonl => serde_json::to_string(&record)?,
+            StreamFormat::Influx => influx_line(&record),
+        };
+        let mut writer = self.writer.lock().unwrap();
+        writeln!(writer, "{}", line)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Observer for ResultStream {
+    fn cycle_completed(&self, run: &RunInfo, cycle: &CycleResult) -> Result<()> {
+        let tags = self.tags(&run.mode, run.cache);
+        self.write(Record::Cycle {
+            tags: &tags,
+            time: now(),
+            cycle: cycle.cycle,
+            bytes: cycle.bytes,
+            ops: cycle.ops,
+            elapsed: cycle.elapsed,
+            throughput: cycle.bytes as f64 / cycle.elapsed,
+            latency: LatencyStatistics::new(&cycle.latencies),
+        })
+    }
+
+    fn run_completed(&self, run: &RunInfo, result: &RunResult) -> Result<()> {
+        let tags = self.tags(&run.mode, run.cache);
+        self.write(Record::Run {
+            tags: &tags,
+            time: now(),
+            cycles: result.cycle_results.len(),
+            statistics: &result.statistics,
+        })
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+fn influx_line(record: &Record) -> String {
+    let (measurement, tags, time, mut fields) = match record {
+        Record::Cycle {
+            tags,
+            time,
+            cycle,
+            bytes,
+            ops,
+            elapsed,
+            throughput,
+            latency,
+        } => {
+            let mut fields = vec![
+                ("cycle", format!("{}i", cycle)),
+                ("bytes", format!("{}i", bytes)),
+                ("ops", format!("{}i", ops)),
+                ("elapsed", elapsed.to_string()),
+                ("throughput", throughput.to_string()),
+            ];
+            fields.extend(latency_fields(latency.as_ref()));
+            ("simple_disk_benchmark_cycle", tags, time, fields)
+        }
+        Record::Run {
+            tags,
+            time,
+            cycles,
+            statistics,
+        } => {
+            let mut fields = vec![
+                ("cycles", format!("{}i", cycles)),
+                ("mean", statistics.mean.to_string()),
+                ("median", statistics.median.to_string()),
+                (
+                    "standard_deviation",
+                    statistics.standard_deviation.to_string(),
+                ),
+                ("min", statistics.min.to_string()),
+                ("max", statistics.max.to_string()),
+                ("total_throughput", statistics.total_throughput.to_string()),
+                ("iops", statistics.iops.to_string()),
+            ];
+            fields.extend(latency_fields(statistics.latency.as_ref()));
+            ("simple_disk_benchmark_run", tags, time, fields)
+        }
+    };
+    // Non-finite floats aren't valid field values.
+    fields.retain(|(_, value)| !matches!(value.as_str(), "NaN" | "inf" | "-inf"));
+
+    let mut line = measurement.to_string();
+    if let Some(host) = &tags.host {
+        write!(line, ",host={}", escape_tag(host)).unwrap();
+    }
+    write!(
+        line,
+        ",path={},mode={},cache={} ",
+        escape_tag(&tags.path),
+        tags.mode.to_string().to_lowercase(),
+        tags.cache.to_string().to_lowercase()
+    )
+    .unwrap();
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    line.push_str(&fields.join(","));
+    write!(line, " {}", (time * 1e9) as u128).unwrap();
+    line
+}
+
+fn latency_fields(latency: Option<&LatencyStatistics>) -> Vec<(&'static str, String)> {
+    match latency {
+        Some(latency) => vec![
+            ("latency_mean", latency.mean.to_string()),
+            ("latency_p50", latency.p50.to_string()),
+            ("latency_p99", latency.p99.to_string()),
+            ("latency_max", latency.max.to_string()),
+        ],
+        None => Vec::new(),
+    }
+}
+
+/// Escapes commas, equals signs and spaces in a tag value.
+fn escape_tag(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, ',' | '=' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// A writer that can be read back after the stream has taken ownership of it.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn cycle() -> CycleResult {
+        CycleResult {
+            cycle: 1,
+            bytes: 4096,
+            elapsed: 0.5,
+            ops: 1,
+            page_faults: None,
+            block_sizes: Vec::new(),
+            samples: Vec::new(),
+            latencies: Default::default(),
+        }
+    }
+
+    fn run(mode: ReadWrite, cache: CachePolicy) -> RunInfo {
+        RunInfo {
+            index: 0,
+            mode,
+            cache,
+            cycles: 2,
+            total_bytes: Some(8192),
+        }
+    }
+
+    #[test]
+    fn test_jsonl() {
+        let output = Shared::default();
+        let stream = ResultStream::new(
+            Box::new(output.clone()),
+            StreamFormat::Jsonl,
+            Path::new("/tmp/test.dat"),
+        );
+        let run = run(ReadWrite::Read, CachePolicy::Direct);
+        stream.cycle_completed(&run, &cycle()).unwrap();
+        stream.cycle_completed(&run, &cycle()).unwrap();
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "cycle");
+        assert_eq!(lines[0]["mode"], "Read");
+        assert_eq!(lines[0]["throughput"], 8192.0);
+    }
+
+    #[test]
+    fn test_influx() {
+        let output = Shared::default();
+        let mut stream = ResultStream::new(
+            Box::new(output.clone()),
+            StreamFormat::Influx,
+            Path::new("/tmp/my test.dat"),
+        );
+        stream.host = Some("host".to_string());
+        stream
+            .cycle_completed(&run(ReadWrite::Write, CachePolicy::Buffered), &cycle())
+            .unwrap();
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(output.starts_with(
+            "simple_disk_benchmark_cycle,host=host,path=/tmp/my\\ test.dat,mode=write,cache=buffered cycle=1i,bytes=4096i,ops=1i,elapsed=0.5,throughput=8192 "
+        ));
+    }
+}
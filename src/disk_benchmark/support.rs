@@ -164,3 +164,167 @@ impl DiskBenchmark for File {
         Ok(())
     }
 }
+
+// MARK: Resource usage
+
+/// Returns the (minor, major) page fault counts of the current process.
+#[cfg(unix)]
+pub fn page_fault_counts() -> Option<(u64, u64)> {
+    let usage = unsafe {
+        let mut usage = std::mem::zeroed::<libc::rusage>();
+        if libc::getrusage(libc::RUSAGE_SELF, &mut usage) != 0 {
+            return None;
+        }
+        usage
+    };
+    Some((usage.ru_minflt as u64, usage.ru_majflt as u64))
+}
+
+#[cfg(target_os = "windows")]
+pub fn page_fault_counts() -> Option<(u64, u64)> {
+    None
+}
+
+/// Returns the name of this machine.
+#[cfg(unix)]
+pub fn hostname() -> Option<String> {
+    let mut buffer = [0u8; 256];
+    let r = unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+    if r != 0 {
+        return None;
+    }
+    let len = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+    String::from_utf8(buffer[..len].to_vec()).ok()
+}
+
+#[cfg(target_os = "windows")]
+pub fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+// MARK: Buffers
+
+/// A zeroed heap buffer aligned for direct I/O (`O_DIRECT` requires aligned memory).
+pub struct AlignedBuffer {
+    ptr: std::ptr::NonNull<u8>,
+    layout: std::alloc::Layout,
+}
+
+impl AlignedBuffer {
+    pub const ALIGNMENT: usize = 4096;
+
+    pub fn new(len: usize) -> Self {
+        let layout = std::alloc::Layout::from_size_align(len.max(1), Self::ALIGNMENT)
+            .expect("Invalid buffer layout.");
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        let ptr =
+            std::ptr::NonNull::new(ptr).unwrap_or_else(|| std::alloc::handle_alloc_error(layout));
+        AlignedBuffer { ptr, layout }
+    }
+}
+
+impl std::ops::Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl std::ops::DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        unsafe { std::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+// MARK: Block devices
+
+/// The block device holding a file, used to report how busy the device is.
+#[derive(Debug, Clone)]
+pub struct BlockDevice {
+    pub name: String,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    major: u32,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    minor: u32,
+}
+
+#[cfg(target_os = "linux")]
+impl BlockDevice {
+    /// The device holding `path`, or its nearest existing parent directory if it hasn't been created yet.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        let dev = path
+            .ancestors()
+            .map(|ancestor| match ancestor.as_os_str().is_empty() {
+                true => Path::new("."),
+                false => ancestor,
+            })
+            .find_map(|ancestor| std::fs::metadata(ancestor).ok())?
+            .dev();
+        let (major, minor) = (libc::major(dev), libc::minor(dev));
+        let diskstats = std::fs::read_to_string("/proc/diskstats").ok()?;
+        diskstats.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            (fields.len() > 3 && fields[0] == major.to_string() && fields[1] == minor.to_string())
+                .then(|| BlockDevice {
+                    name: fields[2].to_string(),
+                    major,
+                    minor,
+                })
+        })
+    }
+
+    /// Milliseconds the device has spent doing I/O since boot (`io_ticks` in `/proc/diskstats`).
+    pub fn io_ticks(&self) -> Option<u64> {
+        let diskstats = std::fs::read_to_string("/proc/diskstats").ok()?;
+        diskstats.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() > 12
+                && fields[0] == self.major.to_string()
+                && fields[1] == self.minor.to_string()
+            {
+                fields[12].parse().ok()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl BlockDevice {
+    pub fn for_path(_path: &Path) -> Option<Self> {
+        None
+    }
+
+    pub fn io_ticks(&self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_block_device_for_missing_file() {
+        // The dashboard resolves the device before the test file is created.
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("missing").join("test.dat");
+        assert!(!missing.exists());
+        let device = BlockDevice::for_path(dir.path());
+        assert_eq!(
+            BlockDevice::for_path(&missing).map(|device| device.name),
+            device.map(|device| device.name)
+        );
+    }
+}
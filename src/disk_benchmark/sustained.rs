@@ -0,0 +1,138 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::ThroughputSample;
+
+/// Budget for a sustained write, writing stops at whichever limit is reached first.
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
+pub struct SustainedOptions {
+    /// Bytes. Writes wrap around to the start of the file at `file_size`, so a budget larger than the file rewrites
+    /// blocks that were already written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<usize>,
+    /// Seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+}
+
+/// Where throughput dropped during a sustained write.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct Knee {
+    /// Seconds since the start of the write.
+    pub time: f64,
+    /// Bytes written before the drop.
+    pub bytes: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+pub struct SustainedAnalysis {
+    /// Mean throughput before the knee, in bytes per second.
+    pub burst_throughput: f64,
+    /// Mean throughput after the knee, in bytes per second.
+    pub steady_throughput: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub knee: Option<Knee>,
+}
+
+impl SustainedAnalysis {
+    /// A drop smaller than this fraction of the burst throughput isn't considered a knee.
+    const MIN_DROP: f64 = 0.2;
+    /// Each side of the knee needs at least this many samples.
+    const MIN_SAMPLES: usize = 2;
+
+    /// Splits the samples into the two segments that best fit a constant throughput each (least squares). If the
+    /// second segment is significantly slower the split is the knee.
+    pub fn new(samples: &[ThroughputSample]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let throughputs: Vec<f64> = samples.iter().map(|s| s.throughput()).collect();
+        let overall = total_throughput(samples);
+
+        let n = throughputs.len();
+        let mut prefix_sum = vec![0.0; n + 1];
+        let mut prefix_square_sum = vec![0.0; n + 1];
+        for (i, t) in throughputs.iter().enumerate() {
+            prefix_sum[i + 1] = prefix_sum[i] + t;
+            prefix_square_sum[i + 1] = prefix_square_sum[i] + t * t;
+        }
+        let sse = |from: usize, to: usize| {
+            let count = (to - from) as f64;
+            let sum = prefix_sum[to] - prefix_sum[from];
+            (prefix_square_sum[to] - prefix_square_sum[from]) - sum * sum / count
+        };
+        let split = (Self::MIN_SAMPLES..=n.saturating_sub(Self::MIN_SAMPLES))
+            .min_by(|a, b| (sse(0, *a) + sse(*a, n)).total_cmp(&(sse(0, *b) + sse(*b, n))));
+
+        if let Some(split) = split {
+            let burst = total_throughput(&samples[..split]);
+            let steady = total_throughput(&samples[split..]);
+            if steady < burst * (1.0 - Self::MIN_DROP) {
+                let before = &samples[split - 1];
+                return Some(SustainedAnalysis {
+                    burst_throughput: burst,
+                    steady_throughput: steady,
+                    knee: Some(Knee {
+                        time: before.time,
+                        bytes: samples[..split].iter().map(|s| s.bytes).sum(),
+                    }),
+                });
+            }
+        }
+        Some(SustainedAnalysis {
+            burst_throughput: overall,
+            steady_throughput: overall,
+            knee: None,
+        })
+    }
+}
+
+fn total_throughput(samples: &[ThroughputSample]) -> f64 {
+    let bytes: usize = samples.iter().map(|s| s.bytes).sum();
+    let duration: f64 = samples.iter().map(|s| s.duration).sum();
+    bytes as f64 / duration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(throughputs: &[usize]) -> Vec<ThroughputSample> {
+        throughputs
+            .iter()
+            .enumerate()
+            .map(|(i, bytes)| ThroughputSample {
+                time: (i + 1) as f64,
+                duration: 1.0,
+                bytes: *bytes,
+                ops: 1,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_knee() {
+        let analysis =
+            SustainedAnalysis::new(&samples(&[1000, 1010, 990, 1000, 1005, 300, 310, 290, 300]))
+                .unwrap();
+        let knee = analysis.knee.unwrap();
+        assert_eq!(knee.time, 5.0);
+        assert_eq!(knee.bytes, 5005);
+        assert_eq!(analysis.burst_throughput, 1001.0);
+        assert_eq!(analysis.steady_throughput, 300.0);
+    }
+
+    #[test]
+    fn test_no_knee() {
+        let analysis =
+            SustainedAnalysis::new(&samples(&[1000, 950, 1020, 980, 1010, 990])).unwrap();
+        assert!(analysis.knee.is_none());
+        assert_eq!(analysis.burst_throughput, analysis.steady_throughput);
+
+        // Throughput going up isn't a knee either.
+        let analysis = SustainedAnalysis::new(&samples(&[300, 300, 300, 1000, 1000])).unwrap();
+        assert!(analysis.knee.is_none());
+
+        assert!(SustainedAnalysis::new(&[]).is_none());
+    }
+}
@@ -0,0 +1,292 @@
+use enum_display_derive::Display;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+/// Why a session failed.
+#[derive(Debug)]
+pub enum Error {
+    /// The options can't be benchmarked, e.g. a block size larger than the file.
+    InvalidOptions(String),
+    /// Creating, opening, reading, writing or deleting the test file failed.
+    File(FileError),
+    /// An I/O error outside of the test file, e.g. opening the result stream.
+    Io(std::io::Error),
+    /// Anything else that went wrong while benchmarking.
+    Benchmark(anyhow::Error),
+}
+
+/// What went wrong with the test file.
+#[derive(Display, PartialEq, Eq, Debug, Clone, Copy, Deserialize, JsonSchema, Serialize, Hash)]
+pub enum ErrorKind {
+    /// The test file couldn't be created or opened.
+    Open,
+    /// Not allowed to create, open, read or write the test file.
+    PermissionDenied,
+    /// The volume is full.
+    NoSpace,
+    /// Direct I/O rejected the operation because its buffer, offset or size isn't aligned to the device's blocks.
+    Alignment,
+    /// A read or write transferred fewer bytes than requested.
+    ShortTransfer,
+    /// The test file isn't what the benchmark left it as, e.g. it was truncated by another process.
+    Verification,
+    /// Any other I/O error.
+    Io,
+}
+
+/// An error with the test file, and where in the benchmark it happened.
+#[derive(Debug)]
+pub struct FileError {
+    pub kind: ErrorKind,
+    pub path: PathBuf,
+    /// Index of the cycle, if it happened during one.
+    pub cycle: Option<usize>,
+    /// Offset of the read or write, if it happened during one.
+    pub offset: Option<usize>,
+    /// What happened, when there's no underlying I/O error.
+    pub detail: Option<String>,
+    pub source: Option<std::io::Error>,
+}
+
+impl Error {
+    /// The process exit status for the error, distinct for each kind so scripts can tell them apart.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Benchmark(_) => 1,
+            Error::InvalidOptions(_) => 2,
+            Error::Io(_) => 3,
+            Error::File(error) => error.kind.exit_code(),
+        }
+    }
+}
+
+impl ErrorKind {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::Open => 10,
+            ErrorKind::PermissionDenied => 11,
+            ErrorKind::NoSpace => 12,
+            ErrorKind::Alignment => 13,
+            ErrorKind::ShortTransfer => 14,
+            ErrorKind::Verification => 15,
+            ErrorKind::Io => 16,
+        }
+    }
+
+    /// The kind of `error`, `fallback` if it says nothing more specific.
+    fn classify(error: &std::io::Error, fallback: ErrorKind) -> ErrorKind {
+        match error.raw_os_error() {
+            Some(libc::ENOSPC | libc::EDQUOT) => ErrorKind::NoSpace,
+            Some(libc::EACCES | libc::EPERM) => ErrorKind::PermissionDenied,
+            _ => fallback,
+        }
+    }
+}
+
+impl FileError {
+    /// An I/O error doing something of `kind` with the file at `path`, the error itself may tell a more specific kind.
+    pub fn new(kind: ErrorKind, path: &Path, source: std::io::Error) -> Self {
+        FileError {
+            kind: ErrorKind::classify(&source, kind),
+            path: path.to_path_buf(),
+            cycle: None,
+            offset: None,
+            detail: None,
+            source: Some(source),
+        }
+    }
+
+    /// An error of `kind` described by `detail`.
+    pub fn with_detail(kind: ErrorKind, path: &Path, detail: String) -> Self {
+        FileError {
+            kind,
+            path: path.to_path_buf(),
+            cycle: None,
+            offset: None,
+            detail: Some(detail),
+            source: None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidOptions(message) => write!(f, "Invalid options: {}", message),
+            Error::File(error) => write!(f, "{}", error),
+            Error::Io(error) => write!(f, "I/O error: {}", error),
+            Error::Benchmark(error) => write!(f, "{:#}", error),
+        }
+    }
+}
+
+impl Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self.kind {
+            ErrorKind::Open => "Failed to open",
+            ErrorKind::PermissionDenied => "Permission denied for",
+            ErrorKind::NoSpace => "No space left for",
+            ErrorKind::Alignment => "Misaligned direct I/O on",
+            ErrorKind::ShortTransfer => "Short transfer on",
+            ErrorKind::Verification => "Verification failed for",
+            ErrorKind::Io => "I/O error on",
+        };
+        write!(f, "{} {}", what, self.path.display())?;
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {}", offset)?;
+        }
+        if let Some(cycle) = self.cycle {
+            write!(f, " in cycle {}", cycle + 1)?;
+        }
+        if let Some(detail) = &self.detail {
+            write!(f, ": {}", detail)?;
+        }
+        if let Some(source) = &self.source {
+            write!(f, ": {}", source)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidOptions(_) => None,
+            Error::File(error) => Some(error),
+            Error::Io(error) => Some(error),
+            Error::Benchmark(error) => Some(error.as_ref()),
+        }
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+/// Errors from the internals are `anyhow` errors, those about the test file become [`Error::File`] and other I/O
+/// errors [`Error::Io`].
+impl From<anyhow::Error> for Error {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<FileError>() {
+            Ok(error) => return Error::File(error),
+            Err(error) => error,
+        };
+        match error.downcast::<std::io::Error>() {
+            Ok(error) => Error::Io(error),
+            Err(error) => Error::Benchmark(error),
+        }
+    }
+}
+
+/// Adds test file context to the internals' results.
+pub(crate) trait FileContext<T> {
+    /// Turns an I/O error into a [`FileError`] of `kind` (or a more specific one) for the file at `path`.
+    fn file_context(self, kind: ErrorKind, path: &Path) -> anyhow::Result<T>;
+
+    /// Records where a [`FileError`] happened, unless it's already known.
+    fn located(self, offset: Option<usize>, cycle: Option<usize>) -> anyhow::Result<T>;
+
+    /// Blames an invalid argument error on misalignment if it happened with `direct` I/O, which reports misaligned
+    /// transfers that way.
+    fn direct_io(self, direct: bool) -> anyhow::Result<T>;
+}
+
+impl<T, E: Into<anyhow::Error>> FileContext<T> for Result<T, E> {
+    fn file_context(self, kind: ErrorKind, path: &Path) -> anyhow::Result<T> {
+        self.map_err(|error| {
+            let error: anyhow::Error = error.into();
+            match error.downcast::<std::io::Error>() {
+                Ok(error) => FileError::new(kind, path, error).into(),
+                Err(error) => error,
+            }
+        })
+    }
+
+    fn located(self, offset: Option<usize>, cycle: Option<usize>) -> anyhow::Result<T> {
+        self.map_err(|error| {
+            let mut error: anyhow::Error = error.into();
+            if let Some(file_error) = error.downcast_mut::<FileError>() {
+                file_error.offset = file_error.offset.or(offset);
+                file_error.cycle = file_error.cycle.or(cycle);
+            }
+            error
+        })
+    }
+
+    fn direct_io(self, direct: bool) -> anyhow::Result<T> {
+        self.map_err(|error| {
+            let mut error: anyhow::Error = error.into();
+            if let Some(file_error) = error.downcast_mut::<FileError>() {
+                let invalid = file_error
+                    .source
+                    .as_ref()
+                    .is_some_and(|source| source.raw_os_error() == Some(libc::EINVAL));
+                if direct && invalid && file_error.kind == ErrorKind::Io {
+                    file_error.kind = ErrorKind::Alignment;
+                }
+            }
+            error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_context() {
+        let path = Path::new("/tmp/test.dat");
+        let result: Result<(), std::io::Error> =
+            Err(std::io::Error::from_raw_os_error(libc::ENOSPC));
+        let error: Error = result
+            .file_context(ErrorKind::Io, path)
+            .located(Some(4096), None)
+            .located(Some(0), Some(2))
+            .unwrap_err()
+            .into();
+        let Error::File(file_error) = &error else {
+            panic!("Expected a file error, got {:?}", error);
+        };
+        assert_eq!(file_error.kind, ErrorKind::NoSpace);
+        assert_eq!(file_error.offset, Some(4096));
+        assert_eq!(file_error.cycle, Some(2));
+        assert_eq!(error.exit_code(), 12);
+        assert!(error
+            .to_string()
+            .starts_with("No space left for /tmp/test.dat at offset 4096 in cycle 3: "));
+
+        let kind = |direct: bool| {
+            let result: Result<(), std::io::Error> =
+                Err(std::io::Error::from_raw_os_error(libc::EINVAL));
+            let error = result
+                .file_context(ErrorKind::Io, path)
+                .direct_io(direct)
+                .unwrap_err();
+            error.downcast_ref::<FileError>().unwrap().kind
+        };
+        assert_eq!(kind(true), ErrorKind::Alignment);
+        assert_eq!(kind(false), ErrorKind::Io);
+
+        let result: Result<(), anyhow::Error> = Err(anyhow::anyhow!("Not I/O."));
+        let error: Error = result
+            .file_context(ErrorKind::Open, path)
+            .unwrap_err()
+            .into();
+        assert!(matches!(error, Error::Benchmark(_)));
+    }
+}
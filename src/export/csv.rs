@@ -0,0 +1,203 @@
+use anyhow::{ensure, Result};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use crate::disk_benchmark::*;
+
+/// Columns describing the session and run, shared by every kind of row.
+const SESSION_COLUMNS: [&str; 10] = [
+    "created",
+    "host",
+    "path",
+    "mode",
+    "cache",
+    "engine",
+    "access",
+    "block_size",
+    "block_sizes",
+    "file_size",
+];
+
+/// Elapsed time is in seconds and throughput in bytes per second.
+const CYCLE_COLUMNS: [&str; 5] = ["cycle", "bytes", "ops", "elapsed", "throughput"];
+
+/// Throughputs are in bytes per second and latencies in seconds.
+const SUMMARY_COLUMNS: [&str; 14] = [
+    "cycles",
+    "mean",
+    "median",
+    "standard_deviation",
+    "min",
+    "max",
+    "total_throughput",
+    "harmonic_mean",
+    "coefficient_of_variation",
+    "latency_mean",
+    "latency_p50",
+    "latency_p99",
+    "latency_max",
+    "outliers",
+];
+
+fn session_columns(session: &SessionResult, run: &RunResult) -> Vec<String> {
+    let options = &session.options;
+    vec![
+        humantime::format_rfc3339_seconds(session.created).to_string(),
+        session.host.clone().unwrap_or_default(),
+        options.path.display().to_string(),
+        run.mode.to_string(),
+        run.cache.to_string(),
+        options.engine.to_string(),
+        if options.random_seek {
+            format!("random ({})", options.distribution)
+        } else {
+            options.pattern.to_string()
+        },
+        options.block_size.to_string(),
+        options
+            .block_sizes
+            .as_ref()
+            .map(|b| b.to_string())
+            .unwrap_or_default(),
+        options.file_size.to_string(),
+    ]
+}
+
+/// Appends one row per cycle of every run to the CSV file at `path`.
+pub fn export_cycles_csv(path: &Path, session: &SessionResult) -> Result<()> {
+    let rows: Vec<Vec<String>> = session
+        .runs
+        .iter()
+        .flat_map(|run| {
+            run.cycle_results.iter().map(move |cycle| {
+                let mut row = session_columns(session, run);
+                row.extend([
+                    cycle.cycle.to_string(),
+                    cycle.bytes.to_string(),
+                    cycle.ops.to_string(),
+                    cycle.elapsed.to_string(),
+                    (cycle.bytes as f64 / cycle.elapsed).to_string(),
+                ]);
+                row
+            })
+        })
+        .collect();
+    append_csv(path, &CYCLE_COLUMNS, &rows)
+}
+
+/// Appends one row of statistics per run to the CSV file at `path`.
+pub fn export_summary_csv(path: &Path, session: &SessionResult) -> Result<()> {
+    let rows: Vec<Vec<String>> = session
+        .runs
+        .iter()
+        .map(|run| {
+            let statistics = &run.statistics;
+            let latency = |f: fn(&LatencyStatistics) -> f64| {
+                statistics
+                    .latency
+                    .as_ref()
+                    .map(|l| f(l).to_string())
+                    .unwrap_or_default()
+            };
+            let mut row = session_columns(session, run);
+            row.extend([
+                run.cycle_results.len().to_string(),
+                statistics.mean.to_string(),
+                statistics.median.to_string(),
+                statistics.standard_deviation.to_string(),
+                statistics.min.to_string(),
+                statistics.max.to_string(),
+                statistics.total_throughput.to_string(),
+                statistics.harmonic_mean.to_string(),
+                statistics.coefficient_of_variation.to_string(),
+                latency(|l| l.mean),
+                latency(|l| l.p50),
+                latency(|l| l.p99),
+                latency(|l| l.max),
+                statistics
+                    .outliers
+                    .iter()
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ]);
+            row
+        })
+        .collect();
+    append_csv(path, &SUMMARY_COLUMNS, &rows)
+}
+
+/// Writes the header and `rows` to a new file, or appends the rows to an existing file if its header matches.
+fn append_csv(path: &Path, columns: &[&str], rows: &[Vec<String>]) -> Result<()> {
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer.write_record(SESSION_COLUMNS.iter().chain(columns))?;
+    let header = String::from_utf8(writer.into_inner()?)?;
+
+    let existing_header = if path.exists() {
+        let mut line = String::new();
+        BufReader::new(File::open(path)?).read_line(&mut line)?;
+        Some(line).filter(|line| !line.is_empty())
+    } else {
+        None
+    };
+    if let Some(existing_header) = &existing_header {
+        ensure!(
+            existing_header == &header,
+            "CSV file {} has different columns, export to a new file.",
+            path.display()
+        );
+        log::warn!("File {} already exists, appending.", path.display());
+    }
+
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut writer = ::csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(file);
+    if existing_header.is_none() {
+        writer.write_record(SESSION_COLUMNS.iter().chain(columns))?;
+    }
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_export_csv() {
+        let tmp_dir = tempdir().unwrap();
+        let mut options = SessionOptions::new(&tmp_dir.path().join("test.dat"), 64 * 1024, 4096, 2);
+        options.dry_run = true;
+        options.no_progress = true;
+        let session = Session::new(options).main().unwrap();
+
+        let path = tmp_dir.path().join("cycles.csv");
+        export_cycles_csv(&path, &session).unwrap();
+        export_cycles_csv(&path, &session).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        // One header and two cycles for each of two modes, twice.
+        assert_eq!(lines.len(), 9);
+        assert!(lines[0].starts_with("created,host,path,mode,cache,"));
+        assert!(lines[0].ends_with(",cycle,bytes,ops,elapsed,throughput"));
+        assert!(lines[1..].iter().all(|l| l.split(',').count() == 15));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("created")).count(), 1);
+
+        // A file with other columns isn't appended to.
+        let summary = tmp_dir.path().join("summary.csv");
+        export_summary_csv(&summary, &session).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&summary).unwrap().lines().count(),
+            3
+        );
+        assert!(export_cycles_csv(&summary, &session).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use std::{fmt::Write, path::Path};
+
+use crate::disk_benchmark::*;
+
+/// Writes a fio job file that reproduces the session's benchmark to `path`. Options without an equivalent in fio are
+/// listed in comments at the top of the file.
+pub fn export_fio(path: &Path, session: &SessionResult) -> Result<()> {
+    std::fs::write(path, fio_job_file(session))?;
+    Ok(())
+}
+
+/// A fio job file with a `[global]` section for the options shared by all runs and one job per run (mode and cache
+/// policy). Jobs are separated by `stonewall` so they run one after the other, like the runs of a session.
+fn fio_job_file(session: &SessionResult) -> String {
+    let options = &session.options;
+    let mut unmapped: Vec<String> = Vec::new();
+    let mut global: Vec<(&str, String)> = vec![
+        ("filename", options.path.display().to_string()),
+        ("size", options.file_size.to_string()),
+    ];
+
+    match &options.block_sizes {
+        Some(block_sizes) => {
+            let total: u32 = block_sizes.sizes.iter().map(|s| s.weight).sum();
+            let mut remaining = 100;
+            let split: Vec<String> = block_sizes
+                .sizes
+                .iter()
+                .enumerate()
+                .map(|(index, s)| {
+                    // fio wants percentages that add up to 100.
+                    let percentage = if index == block_sizes.sizes.len() - 1 {
+                        remaining
+                    } else {
+                        (s.weight * 100 / total).min(remaining)
+                    };
+                    remaining -= percentage;
+                    format!("{}/{}", s.size, percentage)
+                })
+                .collect();
+            global.push(("bssplit", split.join(":")));
+        }
+        None => global.push(("bs", options.block_size.to_string())),
+    }
+
+    match options.engine {
+        Engine::Syscall => global.push(("ioengine", "psync".to_string())),
+        Engine::Mmap => {
+            global.push(("ioengine", "mmap".to_string()));
+            match options.madvise {
+                Some(Advice::Sequential) => global.push(("fadvise_hint", "sequential".to_string())),
+                Some(Advice::Random) => global.push(("fadvise_hint", "random".to_string())),
+                Some(advice) => unmapped.push(format!("--madvise {}", advice)),
+                None => {}
+            }
+            if options.no_msync {
+                unmapped.push("--no-msync".to_string());
+            }
+        }
+    }
+    global.push(("iodepth", "1".to_string()));
+
+    let mut rw_suffix = String::new();
+    if options.random_seek {
+        match options.distribution {
+            OffsetDistribution::Uniform => {}
+            OffsetDistribution::Zipf { theta } => {
+                global.push(("random_distribution", format!("zipf:{}", theta)))
+            }
+            OffsetDistribution::Pareto { h } => {
+                global.push(("random_distribution", format!("pareto:{}", h)))
+            }
+            OffsetDistribution::Normal { mean, stddev } => {
+                let distribution = match mean == 0.5 {
+                    true => format!("normal:{}", stddev * 100.0),
+                    false => format!("normal:{}:{}", stddev * 100.0, mean),
+                };
+                global.push(("random_distribution", distribution));
+            }
+        }
+    } else {
+        match options.pattern {
+            AccessPattern::Sequential => {}
+            AccessPattern::Stride { stride } => {
+                rw_suffix = format!(":{}", stride.saturating_sub(options.block_size));
+                unmapped.push(format!(
+                    "--pattern {}: fio doesn't start each pass over the file one block later",
+                    options.pattern
+                ));
+            }
+            AccessPattern::Reverse | AccessPattern::Interleaved { .. } => {
+                unmapped.push(format!("--pattern {}", options.pattern))
+            }
+        }
+    }
+
+    match &options.sustained {
+        Some(sustained) => {
+            if let Some(bytes) = sustained.bytes {
+                global.push(("io_size", bytes.to_string()));
+            }
+            if let Some(duration) = sustained.duration {
+                global.push(("runtime", format!("{}s", duration)));
+                global.push(("time_based", "1".to_string()));
+            }
+        }
+        None => {
+            global.push(("loops", options.max_cycles().to_string()));
+            if let Some(adaptive) = &options.adaptive {
+                unmapped.push(format!(
+                    "--adaptive: fio always runs all {} loops",
+                    adaptive.max_cycles
+                ));
+            }
+        }
+    }
+
+    if let Some(rate_iops) = options.rate_iops {
+        global.push(("rate_iops", format!("{:.0}", rate_iops)));
+    }
+    if let Some(rate_bytes) = options.rate_bytes {
+        global.push(("rate", rate_bytes.to_string()));
+    }
+    if let Some(sample_interval) = options.sample_interval {
+        global.push(("write_bw_log", "simple-disk-benchmark".to_string()));
+        global.push(("log_avg_msec", format!("{:.0}", sample_interval * 1000.0)));
+    }
+    if options.sample_bytes.is_some() {
+        unmapped.push("--sample-bytes: fio only samples at intervals".to_string());
+    }
+    if options.no_create {
+        unmapped.push("--no-create: fio creates the file if it doesn't exist".to_string());
+    }
+    if options.no_close_file {
+        unmapped.push("--no-close-file".to_string());
+    }
+
+    let mut jobs: Vec<(String, Vec<(&str, String)>)> = session
+        .runs
+        .iter()
+        .map(|run| {
+            let rw = match (&run.mode, options.random_seek) {
+                (ReadWrite::Read, false) => "read",
+                (ReadWrite::Write, false) => "write",
+                (ReadWrite::Read, true) => "randread",
+                (ReadWrite::Write, true) => "randwrite",
+            };
+            let name = format!(
+                "{}-{}",
+                run.mode.to_string().to_lowercase(),
+                run.cache.to_string().to_lowercase()
+            );
+            let direct = if run.cache == CachePolicy::Direct {
+                "1"
+            } else {
+                "0"
+            };
+            (
+                name,
+                vec![
+                    ("rw", format!("{}{}", rw, rw_suffix)),
+                    ("direct", direct.to_string()),
+                    ("stonewall", String::new()),
+                ],
+            )
+        })
+        .collect();
+    if !options.no_delete {
+        if let Some((_, job)) = jobs.last_mut() {
+            job.push(("unlink", "1".to_string()));
+        }
+    }
+
+    let mut file = String::new();
+    writeln!(
+        file,
+        "; Generated by simple-disk-benchmark {} from: {}",
+        env!("CARGO_PKG_VERSION"),
+        session.args
+    )
+    .unwrap();
+    if !unmapped.is_empty() {
+        writeln!(file, ";\n; Options without an equivalent in fio:").unwrap();
+        for option in unmapped.iter() {
+            writeln!(file, ";   {}", option).unwrap();
+        }
+    }
+    let section = |file: &mut String, name: &str, options: &[(&str, String)]| {
+        writeln!(file, "\n[{}]", name).unwrap();
+        for (key, value) in options {
+            if value.is_empty() {
+                writeln!(file, "{}", key).unwrap();
+            } else {
+                writeln!(file, "{}={}", key, value).unwrap();
+            }
+        }
+    };
+    section(&mut file, "global", &global);
+    for (name, job) in jobs.iter() {
+        section(&mut file, name, job);
+    }
+    file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fio_job_file() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("test.dat");
+        let mut options = SessionOptions::new(&path, 64 * 1024, 4096, 2);
+        options.no_progress = true;
+        options.no_delete = false;
+        options.random_seek = true;
+        options.distribution = OffsetDistribution::Zipf { theta: 0.99 };
+        options.adaptive = Some(AdaptiveOptions {
+            max_cycles: 4,
+            window: 2,
+            target_rse: None,
+        });
+        let session = Session::new(options).main().unwrap();
+
+        let file = fio_job_file(&session);
+        assert!(file.contains(&format!(
+            "\n[global]\nfilename={}\nsize=65536\nbs=4096\n",
+            path.display()
+        )));
+        assert!(file.contains("random_distribution=zipf:0.99\n"));
+        assert!(file.contains("loops=4\n"));
+        assert!(file.contains("\n[read-direct]\nrw=randread\ndirect=1\nstonewall\n"));
+        assert!(file.ends_with("\n[write-direct]\nrw=randwrite\ndirect=1\nstonewall\nunlink=1\n"));
+        assert!(file.contains(";   --adaptive: fio always runs all 4 loops\n"));
+    }
+
+    #[test]
+    fn test_fio_job_file_normal_and_mmap() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("test.dat");
+        let mut options = SessionOptions::new(&path, 64 * 1024, 4096, 2);
+        options.no_progress = true;
+        options.random_seek = true;
+        options.distribution = OffsetDistribution::Normal {
+            mean: 0.25,
+            stddev: 0.1,
+        };
+        options.engine = Engine::Mmap;
+        options.no_msync = true;
+        let session = Session::new(options).main().unwrap();
+
+        let file = fio_job_file(&session);
+        assert!(file.contains("random_distribution=normal:10:0.25\n"));
+        assert!(file.contains("ioengine=mmap\n"));
+        assert!(file.contains(";   --no-msync\n"));
+        assert!(!file.contains("--distribution"));
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::{fs::File, path::Path};
+
+use crate::disk_benchmark::*;
+
+/// Writes the session as a JSON report to `path`. If the file exists the session is appended to its reports, which
+/// are upgraded to the current version first.
+pub fn export_json(path: &Path, session: &SessionResult) -> Result<()> {
+    let mut reports: Vec<Report> = if path.exists() {
+        log::warn!("File {} already exists, appending.", path.display());
+        read_reports(path)?.into_iter().map(Report::Read).collect()
+    } else {
+        Vec::new()
+    };
+    reports.push(Report::Session(session));
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(file, &reports)?;
+    Ok(())
+}
+
+/// Sessions read from an existing report are kept as they are, the new session keeps its fields' order.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Report<'a> {
+    Read(serde_json::Value),
+    Session(&'a SessionResult),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_export_json() {
+        let tmp_dir = tempdir().unwrap();
+        let mut options = SessionOptions::new(&tmp_dir.path().join("test.dat"), 64 * 1024, 4096, 2);
+        options.no_progress = true;
+        let session = Session::new(options).main().unwrap();
+
+        // An old report is upgraded before the new session is appended.
+        let path = tmp_dir.path().join("report.json");
+        std::fs::write(
+            &path,
+            include_str!("../disk_benchmark/testdata/report-v0.json"),
+        )
+        .unwrap();
+        export_json(&path, &session).unwrap();
+        let reports: Vec<SessionResult> =
+            serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(|r| r.schema_version == SCHEMA_VERSION));
+    }
+}
@@ -0,0 +1,12 @@
+mod csv;
+mod fio;
+mod json;
+mod prometheus;
+mod report;
+mod svg;
+
+pub use self::csv::{export_cycles_csv, export_summary_csv};
+pub use fio::export_fio;
+pub use json::export_json;
+pub use prometheus::export_prometheus;
+pub use report::{export_html, export_markdown, ReportTemplate};
@@ -0,0 +1,218 @@
+use anyhow::Result;
+use std::{fmt::Write, path::Path};
+
+use crate::disk_benchmark::*;
+
+/// Prefix of every metric name.
+const NAMESPACE: &str = "simple_disk_benchmark";
+
+/// Writes the statistics of every run as OpenMetrics gauges, e.g. for node_exporter's textfile collector.
+///
+/// The file is written next to its destination and then renamed into place, so a collector never reads a partially
+/// written file.
+pub fn export_prometheus(path: &Path, session: &SessionResult) -> Result<()> {
+    let metrics = render_metrics(session);
+    let file_name = path
+        .file_name()
+        .ok_or(anyhow::anyhow!("Invalid path {}", path.display()))?;
+    let temporary = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+    std::fs::write(&temporary, metrics)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// A metric and its samples, each sample has its own labels.
+struct Family {
+    name: &'static str,
+    unit: Option<&'static str>,
+    help: &'static str,
+    samples: Vec<(Vec<(&'static str, String)>, f64)>,
+}
+
+impl Family {
+    fn new(name: &'static str, unit: Option<&'static str>, help: &'static str) -> Self {
+        Family {
+            name,
+            unit,
+            help,
+            samples: Vec::new(),
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        let name = format!("{}_{}", NAMESPACE, self.name);
+        writeln!(out, "# TYPE {name} gauge").unwrap();
+        if let Some(unit) = self.unit {
+            writeln!(out, "# UNIT {name} {unit}").unwrap();
+        }
+        writeln!(out, "# HELP {name} {}", self.help).unwrap();
+        for (labels, value) in self.samples.iter() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape(value)))
+                .collect();
+            writeln!(out, "{name}{{{}}} {}", labels.join(","), value).unwrap();
+        }
+    }
+}
+
+fn render_metrics(session: &SessionResult) -> String {
+    let options = &session.options;
+    let device = session
+        .volume
+        .as_ref()
+        .and_then(|v| v.device())
+        .unwrap_or("");
+    let created = session
+        .created
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64());
+
+    let mut throughput = Family::new(
+        "throughput_bytes_per_second",
+        Some("bytes_per_second"),
+        "Total bytes transferred divided by total elapsed time.",
+    );
+    let mut throughput_stddev = Family::new(
+        "throughput_stddev_bytes_per_second",
+        Some("bytes_per_second"),
+        "Standard deviation of the throughput of each cycle.",
+    );
+    let mut iops = Family::new(
+        "iops",
+        None,
+        "Total operations divided by total elapsed time.",
+    );
+    let mut latency = Family::new(
+        "latency_seconds",
+        Some("seconds"),
+        "Operation latency percentiles, the percentile label is e.g. 99.9.",
+    );
+    let mut latency_mean = Family::new(
+        "latency_mean_seconds",
+        Some("seconds"),
+        "Mean operation latency.",
+    );
+    let mut timestamp = Family::new(
+        "timestamp_seconds",
+        Some("seconds"),
+        "When the benchmark ran, in seconds since the Unix epoch.",
+    );
+    for run in session.runs.iter() {
+        let labels = vec![
+            ("mode", run.mode.to_string().to_lowercase()),
+            ("cache", run.cache.to_string().to_lowercase()),
+            ("block_size", block_size_label(options)),
+            ("path", options.path.display().to_string()),
+            ("device", device.to_string()),
+        ];
+        let statistics = &run.statistics;
+        throughput
+            .samples
+            .push((labels.clone(), statistics.total_throughput));
+        throughput_stddev
+            .samples
+            .push((labels.clone(), statistics.standard_deviation));
+        iops.samples.push((labels.clone(), statistics.iops));
+        if let Some(statistics) = &statistics.latency {
+            // Not a summary's `quantile`, there's no count or sum over the run.
+            for (percentile, value) in [
+                ("50", statistics.p50),
+                ("90", statistics.p90),
+                ("99", statistics.p99),
+                ("99.9", statistics.p999),
+            ] {
+                let mut labels = labels.clone();
+                labels.push(("percentile", percentile.to_string()));
+                latency.samples.push((labels, value));
+            }
+            latency_mean.samples.push((labels.clone(), statistics.mean));
+        }
+        timestamp.samples.push((labels, created));
+    }
+
+    let mut metrics = String::new();
+    for family in [
+        throughput,
+        throughput_stddev,
+        iops,
+        latency,
+        latency_mean,
+        timestamp,
+    ] {
+        if !family.samples.is_empty() {
+            family.write(&mut metrics);
+        }
+    }
+    metrics.push_str("# EOF\n");
+    metrics
+}
+
+/// The block size in bytes, or the distribution as `size:weight` pairs in bytes (e.g. `4096:3,65536:1`).
+fn block_size_label(options: &SessionOptions) -> String {
+    match &options.block_sizes {
+        Some(block_sizes) => block_sizes
+            .sizes
+            .iter()
+            .map(|s| format!("{}:{}", s.size, s.weight))
+            .collect::<Vec<String>>()
+            .join(","),
+        None => options.block_size.to_string(),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_export_prometheus() {
+        let tmp_dir = tempdir().unwrap();
+        let mut options = SessionOptions::new(&tmp_dir.path().join("test.dat"), 64 * 1024, 4096, 2);
+        options.no_progress = true;
+        let session = Session::new(options).main().unwrap();
+
+        let path = tmp_dir.path().join("disk.prom");
+        export_prometheus(&path, &session).unwrap();
+        let metrics = std::fs::read_to_string(&path).unwrap();
+        assert!(metrics.ends_with("# EOF\n"));
+        let throughput: Vec<&str> = metrics
+            .lines()
+            .filter(|l| l.starts_with("simple_disk_benchmark_throughput_bytes_per_second{"))
+            .collect();
+        assert_eq!(throughput.len(), 2);
+        assert!(throughput[0].contains("mode=\"read\",cache=\"direct\",block_size=\"4096\""));
+        assert_eq!(
+            metrics
+                .lines()
+                .filter(|l| l.starts_with("simple_disk_benchmark_latency_seconds{"))
+                .count(),
+            8
+        );
+        assert!(metrics.contains("percentile=\"99.9\""));
+        assert!(!metrics.contains("quantile="));
+        // The temporary file has been renamed into place.
+        assert!(!tmp_dir.path().join(".disk.prom.tmp").exists());
+    }
+
+    #[test]
+    fn test_block_size_label() {
+        let mut options = SessionOptions::new(Path::new("test.dat"), 64 * 1024, 4096, 2);
+        assert_eq!(block_size_label(&options), "4096");
+        options.block_sizes = Some(parse_block_sizes("4KB:3,64KB:1").unwrap());
+        assert_eq!(block_size_label(&options), "4096:3,65536:1");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use minijinja::{context, value::Value, Environment};
+use std::path::Path;
+
+use super::svg::{line_chart, Series};
+use crate::disk_benchmark::*;
+use crate::support::*;
+
+const MARKDOWN_TEMPLATE: &str = include_str!("templates/report.md");
+const HTML_TEMPLATE: &str = include_str!("templates/report.html");
+
+/// Writes a Markdown report of the session to `path`.
+pub fn export_markdown(path: &Path, session: &SessionResult) -> Result<()> {
+    let report = render_report("report.md", MARKDOWN_TEMPLATE, session)?;
+    std::fs::write(path, report)?;
+    Ok(())
+}
+
+/// Writes a self-contained HTML report of the session, with inline SVG charts, to `path`.
+pub fn export_html(path: &Path, session: &SessionResult) -> Result<()> {
+    let report = render_report("report.html", HTML_TEMPLATE, session)?;
+    std::fs::write(path, report)?;
+    Ok(())
+}
+
+/// A user supplied template file to render sessions with (see `docs/templates.md` for the context).
+#[derive(Debug, Clone)]
+pub struct ReportTemplate {
+    name: String,
+    source: String,
+}
+
+impl ReportTemplate {
+    /// Reads and compiles the template, so a missing file or a syntax error shows up before benchmarking.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read template {}", path.display()))?;
+        let name = path
+            .file_name()
+            .map_or("template".to_string(), |n| n.to_string_lossy().to_string());
+        environment()
+            .template_from_named_str(&name, &source)
+            .with_context(|| format!("Failed to parse template {}", path.display()))?;
+        Ok(ReportTemplate { name, source })
+    }
+
+    pub fn render(&self, session: &SessionResult) -> Result<String> {
+        render_report(&self.name, &self.source, session)
+    }
+}
+
+/// The template's name decides how values are escaped (e.g. `.html`).
+fn render_report(name: &str, template: &str, session: &SessionResult) -> Result<String> {
+    let mut env = environment();
+    env.add_template(name, template)?;
+    Ok(env.get_template(name)?.render(report_context(session))?)
+}
+
+/// An environment with filters for formatting benchmark values: `datasize` (bytes as e.g. `1.5 GB`) and `latency`
+/// (seconds as e.g. `12.30 µs`).
+fn environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    env.add_filter("datasize", |value: f64| {
+        DataSize::from(value).to_human_string()
+    });
+    env.add_filter("latency", format_latency);
+    env
+}
+
+fn report_context(session: &SessionResult) -> Value {
+    let options = &session.options;
+    let runs: Vec<Value> = session
+        .runs
+        .iter()
+        .map(|run| {
+            let latencies: Vec<LatencyStatistics> = run
+                .cycle_results
+                .iter()
+                .filter_map(|c| LatencyStatistics::new(&c.latencies))
+                .collect();
+            let latency_chart = (!latencies.is_empty()).then(|| {
+                Value::from_safe_string(line_chart(
+                    &[
+                        Series {
+                            name: "p50",
+                            color: "#2ca02c",
+                            values: latencies.iter().map(|l| l.p50).collect(),
+                        },
+                        Series {
+                            name: "p99",
+                            color: "#d62728",
+                            values: latencies.iter().map(|l| l.p99).collect(),
+                        },
+                    ],
+                    format_latency,
+                ))
+            });
+            let throughput_chart = Value::from_safe_string(line_chart(
+                &[Series {
+                    name: "Throughput",
+                    color: "#1f77b4",
+                    values: run
+                        .cycle_results
+                        .iter()
+                        .map(|c| c.bytes as f64 / c.elapsed)
+                        .collect(),
+                }],
+                |v| format!("{}/s", DataSize::from(v).to_human_string()),
+            ));
+            context! {
+                mode => run.mode.to_string(),
+                cache => run.cache.to_string(),
+                cycles => run.cycle_results.len(),
+                statistics => Value::from_serialize(&run.statistics),
+                throughput_chart,
+                latency_chart,
+            }
+        })
+        .collect();
+
+    context! {
+        session => Value::from_serialize(session),
+        created => humantime::format_rfc3339_seconds(session.created).to_string(),
+        os => os_info::get().to_string(),
+        access => if options.random_seek {
+            format!("random ({})", options.distribution)
+        } else {
+            options.pattern.to_string()
+        },
+        block_size => match &options.block_sizes {
+            Some(block_sizes) => block_sizes.to_string(),
+            None => DataSize::from(options.block_size).to_human_string(),
+        },
+        runs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_reports() {
+        let tmp_dir = tempdir().unwrap();
+        let mut options = SessionOptions::new(&tmp_dir.path().join("test.dat"), 64 * 1024, 4096, 2);
+        options.no_progress = true;
+        let session = Session::new(options).main().unwrap();
+
+        let markdown = render_report("report.md", MARKDOWN_TEMPLATE, &session).unwrap();
+        assert!(markdown.starts_with("# Disk Benchmark Report"));
+        assert!(markdown.contains("| Read | Direct | 2 |"));
+        assert!(markdown.contains("| Write | Direct | 2 |"));
+
+        let html = render_report("report.html", HTML_TEMPLATE, &session).unwrap();
+        assert_eq!(html.matches("<svg ").count(), 4);
+        assert!(html.contains("<td>Read</td>"));
+
+        let template = tmp_dir.path().join("summary.txt");
+        std::fs::write(
+            &template,
+            "{% for run in runs %}{{ run.mode }}: {{ run.statistics.mean|datasize }}/s\n{% endfor %}",
+        )
+        .unwrap();
+        let summary = ReportTemplate::load(&template)
+            .unwrap()
+            .render(&session)
+            .unwrap();
+        assert_eq!(summary.lines().count(), 2);
+        assert!(summary.starts_with("Read: "));
+
+        std::fs::write(&template, "{% for run in runs %}").unwrap();
+        assert!(ReportTemplate::load(&template).is_err());
+    }
+}
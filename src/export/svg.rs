@@ -0,0 +1,145 @@
+use std::fmt::Write;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 200.0;
+/// Room for the axis labels.
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_BOTTOM: f64 = 24.0;
+const MARGIN_TOP: f64 = 24.0;
+
+/// One line of a chart.
+#[derive(Debug)]
+pub struct Series<'a> {
+    pub name: &'a str,
+    pub color: &'a str,
+    pub values: Vec<f64>,
+}
+
+/// A self-contained SVG line chart of `series` against the cycle index. `format` labels the y axis.
+pub fn line_chart<F>(series: &[Series], format: F) -> String
+where
+    F: Fn(f64) -> String,
+{
+    let count = series.iter().map(|s| s.values.len()).max().unwrap_or(0);
+    let max = series
+        .iter()
+        .flat_map(|s| s.values.iter())
+        .cloned()
+        .fold(0.0, f64::max);
+    let plot_width = WIDTH - MARGIN_LEFT - 8.0;
+    let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let x = |i: usize| {
+        MARGIN_LEFT
+            + if count > 1 {
+                i as f64 / (count - 1) as f64 * plot_width
+            } else {
+                plot_width / 2.0
+            }
+    };
+    let y = |v: f64| {
+        MARGIN_TOP + plot_height
+            - if max > 0.0 {
+                v / max * plot_height
+            } else {
+                0.0
+            }
+    };
+
+    let mut svg = String::new();
+    write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {WIDTH} {HEIGHT}" width="{WIDTH}" height="{HEIGHT}" font-family="sans-serif" font-size="11">"#
+    )
+    .unwrap();
+    // Axes and labels.
+    let bottom = MARGIN_TOP + plot_height;
+    write!(
+        svg,
+        r##"<path d="M{MARGIN_LEFT} {MARGIN_TOP}V{bottom}H{}" fill="none" stroke="#888"/>"##,
+        WIDTH - 8.0
+    )
+    .unwrap();
+    write!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="end">{}</text><text x="{}" y="{bottom}" text-anchor="end">{}</text>"#,
+        MARGIN_LEFT - 4.0,
+        MARGIN_TOP + 4.0,
+        format(max),
+        MARGIN_LEFT - 4.0,
+        format(0.0),
+    )
+    .unwrap();
+    write!(
+        svg,
+        r#"<text x="{MARGIN_LEFT}" y="{}">1</text><text x="{}" y="{}" text-anchor="end">{count}</text>"#,
+        HEIGHT - 6.0,
+        WIDTH - 8.0,
+        HEIGHT - 6.0,
+    )
+    .unwrap();
+    write!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle">Cycle</text>"#,
+        MARGIN_LEFT + plot_width / 2.0,
+        HEIGHT - 6.0,
+    )
+    .unwrap();
+
+    for (index, s) in series.iter().enumerate() {
+        let points: Vec<String> = s
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| format!("{:.1},{:.1}", x(i), y(*v)))
+            .collect();
+        write!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+            points.join(" "),
+            s.color
+        )
+        .unwrap();
+        for point in points.iter() {
+            let (cx, cy) = point.split_once(',').unwrap();
+            write!(
+                svg,
+                r#"<circle cx="{cx}" cy="{cy}" r="2.5" fill="{}"/>"#,
+                s.color
+            )
+            .unwrap();
+        }
+        write!(
+            svg,
+            r#"<text x="{}" y="14" fill="{}">{}</text>"#,
+            MARGIN_LEFT + index as f64 * 120.0,
+            s.color,
+            s.name
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_chart() {
+        let svg = line_chart(
+            &[Series {
+                name: "Throughput",
+                color: "#1f77b4",
+                values: vec![1.0, 2.0, 4.0],
+            }],
+            |v| format!("{v}"),
+        );
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 3);
+        // The largest value is at the top of the plot area, zero at the bottom.
+        assert!(svg.contains(&format!("{:.1},{:.1}", WIDTH - 8.0, MARGIN_TOP)));
+        assert!(svg.contains(">Throughput</text>"));
+    }
+}
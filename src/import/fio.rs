@@ -0,0 +1,359 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::disk_benchmark::*;
+
+/// The parts of fio's `--output-format=json` that map to a session.
+#[derive(Debug, Deserialize)]
+struct FioOutput {
+    #[serde(rename = "fio version")]
+    version: String,
+    timestamp: u64,
+    #[serde(default)]
+    timestamp_ms: Option<u64>,
+    #[serde(rename = "global options", default)]
+    global_options: HashMap<String, String>,
+    jobs: Vec<FioJob>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FioJob {
+    jobname: String,
+    #[serde(default)]
+    hostname: Option<String>,
+    #[serde(default)]
+    error: i64,
+    #[serde(rename = "job options", default)]
+    options: HashMap<String, String>,
+    read: FioDirection,
+    write: FioDirection,
+}
+
+/// Bandwidths are in KiB/s (except `bw_bytes`), `runtime` in milliseconds.
+#[derive(Debug, Deserialize)]
+struct FioDirection {
+    io_bytes: u64,
+    bw_bytes: f64,
+    iops: f64,
+    runtime: f64,
+    total_ios: u64,
+    #[serde(default)]
+    bw_min: f64,
+    #[serde(default)]
+    bw_max: f64,
+    #[serde(default)]
+    bw_dev: f64,
+    #[serde(default)]
+    clat_ns: Option<FioLatency>,
+}
+
+/// Completion latency in nanoseconds. Percentiles are keyed by e.g. `"99.900000"`.
+#[derive(Debug, Deserialize)]
+struct FioLatency {
+    min: f64,
+    max: f64,
+    mean: f64,
+    #[serde(default)]
+    percentile: Option<BTreeMap<String, f64>>,
+}
+
+/// Reads fio's JSON output (`fio --output-format=json`) from `path` as one session per job, each with a run for
+/// every direction that transferred data.
+///
+/// fio doesn't run cycles, each run has a single cycle covering the whole job. The throughput statistics come from
+/// fio's bandwidth samples, latency statistics from its completion latency percentiles.
+pub fn import_fio(path: &Path) -> Result<Vec<SessionResult>> {
+    let file = std::fs::File::open(path)?;
+    let output: FioOutput = serde_json::from_reader(std::io::BufReader::new(file))
+        .with_context(|| format!("Failed to read fio output {}", path.display()))?;
+    let created = UNIX_EPOCH
+        + output
+            .timestamp_ms
+            .map_or(Duration::from_secs(output.timestamp), Duration::from_millis);
+
+    output
+        .jobs
+        .iter()
+        .filter(|job| {
+            if job.error != 0 {
+                log::warn!(
+                    "Skipping fio job {}, it failed ({}).",
+                    job.jobname,
+                    job.error
+                );
+            }
+            job.error == 0
+        })
+        .map(|job| {
+            let option = |name: &str| {
+                job.options
+                    .get(name)
+                    .or(output.global_options.get(name))
+                    .map(|s| s.as_str())
+            };
+            let cache = if option("direct") == Some("1") {
+                CachePolicy::Direct
+            } else {
+                CachePolicy::Buffered
+            };
+            let read_block_size = fio_block_size(option("bs"), &ReadWrite::Read)?;
+            let write_block_size = fio_block_size(option("bs"), &ReadWrite::Write)?;
+            let runs: Vec<RunResult> = [
+                (ReadWrite::Read, &job.read, read_block_size),
+                (ReadWrite::Write, &job.write, write_block_size),
+            ]
+            .into_iter()
+            .filter(|(_, direction, _)| direction.io_bytes > 0)
+            .map(|(mode, direction, block_size)| {
+                let mut run = run_result(mode, cache, direction);
+                if read_block_size != write_block_size {
+                    record_block_size(&mut run, block_size, direction);
+                }
+                run
+            })
+            .collect();
+            let block_size = match runs.first() {
+                Some(run) if run.mode == ReadWrite::Write => write_block_size,
+                _ => read_block_size,
+            };
+            // Sizes such as `100%` are relative to the device, use what was transferred instead.
+            let file_size = option("size")
+                .and_then(|size| parse_fio_size(size).ok())
+                .unwrap_or_else(|| {
+                    runs.iter()
+                        .map(|r| r.cycle_results[0].bytes)
+                        .max()
+                        .unwrap_or(0)
+                });
+            let mut options = SessionOptions::builder(option("filename").unwrap_or(&job.jobname))
+                .modes(runs.iter().map(|r| r.mode.clone()).collect())
+                .file_size(file_size)
+                .block_size(block_size)
+                .cycles(1)
+                .progress(true)
+                .cache(cache);
+            if option("rw").is_some_and(|rw| rw.starts_with("rand")) {
+                options = options.random(OffsetDistribution::default());
+            }
+            let options = options.build_unchecked();
+            Ok(SessionResult {
+                schema_version: SCHEMA_VERSION,
+                args: format!("fio job {} ({})", job.jobname, path.display()),
+                created,
+                host: job.hostname.clone(),
+                source: Some(output.version.clone()),
+                interrupted: false,
+                failures: Vec::new(),
+                volume: None,
+                options,
+                runs,
+            })
+        })
+        .collect()
+}
+
+fn run_result(mode: ReadWrite, cache: CachePolicy, direction: &FioDirection) -> RunResult {
+    let throughput = direction.bw_bytes;
+    let standard_deviation = direction.bw_dev * 1024.0;
+    let latency = direction.clat_ns.as_ref().and_then(|clat| {
+        let percentiles = clat.percentile.as_ref()?;
+        let percentile = |p: &str| percentiles.get(p).map(|ns| ns / 1e9);
+        Some(LatencyStatistics {
+            mean: clat.mean / 1e9,
+            min: clat.min / 1e9,
+            p50: percentile("50.000000")?,
+            p90: percentile("90.000000")?,
+            p99: percentile("99.000000")?,
+            p999: percentile("99.900000")?,
+            max: clat.max / 1e9,
+        })
+    });
+    RunResult {
+        mode,
+        cache,
+        cycle_results: vec![CycleResult {
+            cycle: 0,
+            bytes: direction.io_bytes as usize,
+            elapsed: direction.runtime / 1000.0,
+            ops: direction.total_ios as usize,
+            page_faults: None,
+            block_sizes: Vec::new(),
+            samples: Vec::new(),
+            latencies: LatencyHistogram::default(),
+        }],
+        statistics: RunStatistics {
+            mean: throughput,
+            median: throughput,
+            standard_deviation,
+            min: direction.bw_min * 1024.0,
+            max: direction.bw_max * 1024.0,
+            total_throughput: throughput,
+            iops: direction.iops,
+            harmonic_mean: throughput,
+            coefficient_of_variation: standard_deviation / throughput,
+            mean_interval: None,
+            median_interval: None,
+            outliers: Vec::new(),
+            block_sizes: Vec::new(),
+            latency,
+        },
+        sustained: None,
+        convergence: None,
+    }
+}
+
+/// The block size of a direction from fio's `bs` option, which can give one size for all directions or one per
+/// direction (`4k,64k` reads 4k and writes 64k blocks). fio's default is 4k.
+fn fio_block_size(bs: Option<&str>, mode: &ReadWrite) -> Result<usize> {
+    let Some(bs) = bs else {
+        return Ok(4096);
+    };
+    let sizes: Vec<&str> = bs.split(',').collect();
+    let index = match mode {
+        ReadWrite::Read => 0,
+        ReadWrite::Write => 1,
+    };
+    match sizes.get(index).or(sizes.first()) {
+        Some(size) if size.trim().is_empty() => Ok(4096),
+        Some(size) => parse_fio_size(size),
+        None => Ok(4096),
+    }
+}
+
+/// Keeps the block size of a run, for jobs that read and write blocks of different sizes.
+fn record_block_size(run: &mut RunResult, block_size: usize, direction: &FioDirection) {
+    let cycle = &mut run.cycle_results[0];
+    cycle.block_sizes = vec![BlockSizeResult {
+        block_size,
+        ops: cycle.ops,
+        bytes: cycle.bytes,
+        elapsed: cycle.elapsed,
+    }];
+    run.statistics.block_sizes = vec![BlockSizeStatistics {
+        block_size,
+        ops: cycle.ops,
+        bytes: cycle.bytes,
+        throughput: direction.bw_bytes,
+        mean_latency: direction
+            .clat_ns
+            .as_ref()
+            .map_or(cycle.elapsed / cycle.ops as f64, |clat| clat.mean / 1e9),
+    }];
+}
+
+/// Parses a fio size such as `4k` or `1G`, suffixes are powers of 1024. For ranges and per direction sizes (`4k-64k`,
+/// `4k,8k`) the first size is used.
+fn parse_fio_size(size: &str) -> Result<usize> {
+    let size = size
+        .split([',', '-', ':'])
+        .next()
+        .unwrap_or(size)
+        .trim()
+        .to_lowercase();
+    let size = size.trim_end_matches('b').trim_end_matches('i');
+    let (digits, multiplier) = match size.chars().last() {
+        Some('k') => (&size[..size.len() - 1], 1 << 10),
+        Some('m') => (&size[..size.len() - 1], 1 << 20),
+        Some('g') => (&size[..size.len() - 1], 1 << 30),
+        Some('t') => (&size[..size.len() - 1], 1 << 40),
+        Some('p') => (&size[..size.len() - 1], 1 << 50),
+        _ => (size, 1),
+    };
+    let value: usize = digits
+        .parse()
+        .map_err(|_| anyhow!("Invalid fio size {}", size))?;
+    Ok(value * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_import_fio() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("fio.json");
+        std::fs::write(&path, include_str!("testdata/fio.json")).unwrap();
+        let sessions = import_fio(&path).unwrap();
+        assert_eq!(sessions.len(), 2);
+
+        let read = &sessions[0];
+        assert_eq!(read.source.as_deref(), Some("fio-3.33"));
+        assert_eq!(read.options.path, PathBuf::from("/mnt/data/fio.dat"));
+        assert_eq!(read.options.block_size, 128 * 1024);
+        assert_eq!(read.options.file_size, 1 << 30);
+        assert!(!read.options.random_seek);
+        assert_eq!(read.runs.len(), 1);
+        let run = &read.runs[0];
+        assert_eq!(run.mode, ReadWrite::Read);
+        assert_eq!(run.cache, CachePolicy::Direct);
+        assert_eq!(run.statistics.total_throughput, 1789569706.0);
+        assert_eq!(run.statistics.min, 1613824.0 * 1024.0);
+        assert_eq!(run.cycle_results[0].elapsed, 30.0);
+        let latency = run.statistics.latency.as_ref().unwrap();
+        assert_eq!(latency.p99, 0.003293184);
+
+        let mixed = &sessions[1];
+        assert!(mixed.options.random_seek);
+        assert_eq!(mixed.options.block_size, 4096);
+        assert_eq!(mixed.options.modes, vec![ReadWrite::Read, ReadWrite::Write]);
+        assert!(mixed.runs.iter().all(|r| r.cache == CachePolicy::Buffered));
+        // Percentiles weren't reported for the writes.
+        assert!(mixed.runs[1].statistics.latency.is_none());
+    }
+
+    #[test]
+    fn test_import_fio_sizes() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("fio.json");
+        let output = include_str!("testdata/fio.json")
+            .replace(r#""size" : "1G""#, r#""size" : "100%""#)
+            .replace(r#""bs" : "4k""#, r#""bs" : "4k,64k""#);
+        std::fs::write(&path, output).unwrap();
+        let sessions = import_fio(&path).unwrap();
+
+        assert_eq!(sessions[0].options.file_size, 53687091200);
+        let mixed = &sessions[1];
+        assert_eq!(mixed.options.block_size, 4096);
+        let block_size = |run: &RunResult| run.statistics.block_sizes[0].block_size;
+        assert_eq!(block_size(&mixed.runs[0]), 4096);
+        assert_eq!(block_size(&mixed.runs[1]), 64 * 1024);
+    }
+
+    #[test]
+    fn test_parse_fio_size() {
+        assert_eq!(parse_fio_size("4096").unwrap(), 4096);
+        assert_eq!(parse_fio_size("4k").unwrap(), 4096);
+        assert_eq!(parse_fio_size("128KiB").unwrap(), 128 * 1024);
+        assert_eq!(parse_fio_size("1G").unwrap(), 1 << 30);
+        assert_eq!(parse_fio_size("4k-64k").unwrap(), 4096);
+        assert_eq!(parse_fio_size("4k,8k").unwrap(), 4096);
+        assert!(parse_fio_size("lots").is_err());
+        assert!(parse_fio_size("100%").is_err());
+    }
+
+    #[test]
+    fn test_fio_block_size() {
+        assert_eq!(fio_block_size(None, &ReadWrite::Write).unwrap(), 4096);
+        assert_eq!(fio_block_size(Some("8k"), &ReadWrite::Write).unwrap(), 8192);
+        assert_eq!(
+            fio_block_size(Some("4k,64k"), &ReadWrite::Read).unwrap(),
+            4096
+        );
+        assert_eq!(
+            fio_block_size(Some("4k,64k"), &ReadWrite::Write).unwrap(),
+            64 * 1024
+        );
+        assert_eq!(
+            fio_block_size(Some("8k,"), &ReadWrite::Write).unwrap(),
+            4096
+        );
+    }
+}
@@ -0,0 +1,3 @@
+mod fio;
+
+pub use fio::import_fio;
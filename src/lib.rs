@@ -0,0 +1,33 @@
+//! Benchmark disk performance: read and write a test file in cycles of blocks and measure throughput and latency.
+//!
+//! ```
+//! use simple_disk_benchmark::{ReadWrite, Session, SessionOptions};
+//!
+//! let path = std::env::temp_dir().join("simple-disk-benchmark-doctest.dat");
+//! let options = SessionOptions::builder(&path)
+//!     .file_size(1024 * 1024)
+//!     .block_size(64 * 1024)
+//!     .cycles(3)
+//!     .modes(vec![ReadWrite::Read])
+//!     .build()?;
+//! let result = Session::new(options).main()?;
+//! assert_eq!(result.runs[0].cycle_results.len(), 3);
+//! assert!(result.runs[0].statistics.mean > 0.0);
+//! # Ok::<(), simple_disk_benchmark::Error>(())
+//! ```
+
+pub mod charts;
+pub mod colored_markup;
+pub mod database;
+pub mod disk_benchmark;
+mod error;
+pub mod export;
+pub mod import;
+pub mod support;
+pub mod volume;
+
+pub use disk_benchmark::{
+    CachePolicy, CycleResult, Engine, LatencyStatistics, Observer, ReadWrite, RunFailure, RunInfo,
+    RunResult, RunStatistics, Session, SessionOptions, SessionOptionsBuilder, SessionResult,
+};
+pub use error::{Error, ErrorKind, FileError};
@@ -4,24 +4,27 @@ use clap_verbosity_flag::{Verbosity, WarnLevel};
 use enum_display_derive::Display;
 use fern::colors::{Color, ColoredLevelConfig};
 use minijinja::{context, Environment};
+use std::io::IsTerminal;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
 use std::time::SystemTime;
-use std::{collections::HashSet, fmt::Display, fs::File, path::PathBuf, vec};
+use std::{collections::HashSet, fmt::Display, path::PathBuf, vec};
 
-mod colored_markup;
-mod disk_benchmark;
-mod support;
-mod volume;
-
-use colored_markup::*;
-use disk_benchmark::*;
-use support::*;
+use simple_disk_benchmark::{
+    charts::*, colored_markup::*, database::*, disk_benchmark::*, export::*, import::*, support::*,
+};
 
 // Based partly on: From <https://www.geschke-online.de/sdb/sdb.1.html>
 
 /// A simple tool for benchmarking disk performance.
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// File to use for benchmarking. If this file exists it will be deleted.
     #[arg(value_name = "FILE", default_value = "testfile.dat")]
     path: PathBuf,
@@ -34,18 +37,66 @@ struct Args {
     #[arg(short, long = "blocksize", value_parser = parse_data_size, default_value = "128MB")]
     block_size: DataSize<usize>,
 
+    /// Distribution of block sizes, each read/write picks its size from it. Either a weighted list (4KB:50,64KB:30,1MB:20) or a range of power of two sizes (4KB-1MB). Every size must be a multiple of the smallest one, which offsets are aligned to. Overrides --blocksize.
+    #[arg(long, value_name = "SIZES", value_parser = parse_block_sizes)]
+    block_sizes: Option<BlockSizes>,
+
+    /// Limit each cycle to this many read/write operations per second.
+    #[arg(long, value_name = "IOPS")]
+    rate_iops: Option<f64>,
+
+    /// Limit each cycle to this many bytes per second.
+    #[arg(long, value_name = "SIZE", value_parser = parse_data_size)]
+    rate_bytes: Option<DataSize<usize>>,
+
     /// Number of test cycles to run.
     #[arg(short, long, default_value_t = 10)]
     cycles: i32,
 
+    /// Keep running cycles until throughput reaches a steady state (range within 20% and slope within 10% of the average over --steady-window cycles) or --target-rse is met, instead of running a fixed number of cycles.
+    #[arg(long, default_value_t = false, conflicts_with = "sustained")]
+    adaptive: bool,
+
+    /// Maximum number of cycles an adaptive run performs.
+    #[arg(long, default_value_t = 100, requires = "adaptive")]
+    max_cycles: usize,
+
+    /// Number of most recent cycles the steady state criterion is evaluated over.
+    #[arg(long, default_value_t = 5, requires = "adaptive")]
+    steady_window: usize,
+
+    /// Also stop an adaptive run once the relative standard error of the mean throughput is at or below this fraction (e.g. 0.01).
+    #[arg(long, value_name = "FRACTION", requires = "adaptive")]
+    target_rse: Option<f64>,
+
     /// Types of test to run: read, write or all.
     #[arg(short, long, default_value = "all")]
     mode: Vec<Mode>,
 
-    /// Seek to a random position in the file before each read/write.
+    /// Seek to a random position, picked from --distribution, before each read/write. Earlier versions seeked once per cycle and then read/wrote sequentially.
     #[arg(short, long)]
     random_seek: bool,
 
+    /// Distribution of random offsets: uniform, zipf[:THETA], pareto[:H] or normal:MEAN,STDDEV (as fractions of the file size).
+    #[arg(long, value_name = "DISTRIBUTION", value_parser = parse_offset_distribution, default_value = "uniform", requires = "random_seek")]
+    distribution: OffsetDistribution,
+
+    /// Order of non-random reads/writes: sequential, reverse, stride:SIZE (distance between the start of each block) or interleaved:N (N sequential streams).
+    #[arg(long, value_name = "PATTERN", value_parser = parse_access_pattern, default_value = "sequential", conflicts_with = "random_seek")]
+    pattern: AccessPattern,
+
+    /// Write continuously until --sustained-bytes or --sustained-duration is reached and detect where throughput drops (e.g. when an SSD's write cache fills up). Implies --mode write, a single cycle and --chart throughput.
+    #[arg(long, default_value_t = false)]
+    sustained: bool,
+
+    /// Stop a sustained write after this many bytes. The write wraps around to the start of the file once it reaches --size, so a budget larger than the file rewrites the same blocks; use a --size at least as large to write fresh blocks only.
+    #[arg(long, value_name = "SIZE", value_parser = parse_data_size, requires = "sustained")]
+    sustained_bytes: Option<DataSize<usize>>,
+
+    /// Stop a sustained write after this long (e.g. 5m).
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration, requires = "sustained")]
+    sustained_duration: Option<std::time::Duration>,
+
     /// Do not create the test file, the file must already exist.
     #[arg(long, default_value_t = false)]
     no_create: bool,
@@ -58,10 +109,30 @@ struct Args {
     #[arg(long, default_value_t = false)]
     no_progress: bool,
 
+    /// Show a full screen dashboard with live throughput, IOPS, latency and device utilization instead of the progress bar. Falls back to the progress bar when stdout isn't a terminal.
+    #[arg(long, default_value_t = false, conflicts_with = "no_progress")]
+    dashboard: bool,
+
     /// Do not disable the file system cache.
     #[arg(long, default_value_t = false)]
     no_disable_cache: bool,
 
+    /// Run each mode twice, with the file system cache disabled and enabled, and compare the results.
+    #[arg(long, default_value_t = false, conflicts_with = "no_disable_cache")]
+    compare_cache: bool,
+
+    /// How to read and write the test file: with read/write system calls or through a memory mapping.
+    #[arg(long, value_enum, default_value_t = EngineArg::Syscall)]
+    engine: EngineArg,
+
+    /// Access pattern hint passed to madvise when using the mmap engine.
+    #[arg(long, value_enum, value_name = "ADVICE")]
+    madvise: Option<AdviceArg>,
+
+    /// Do not msync the mapping at the end of each write cycle when using the mmap engine, so the writes may still be in the page cache when the cycle ends.
+    #[arg(long, default_value_t = false)]
+    no_msync: bool,
+
     /// Do not close the file after each cycle.
     #[arg(long, default_value_t = false)]
     no_close_file: bool,
@@ -74,10 +145,70 @@ struct Args {
     #[arg(short = 'X', long)]
     no_chart: bool,
 
+    /// What to chart: the elapsed time of each cycle, throughput over time (implies --sample-interval 100ms if no sampling option is given), or a histogram and CDF of operation latencies for each run.
+    #[arg(long, value_enum, default_value_t = Chart::Elapsed)]
+    chart: Chart,
+
+    /// Sample throughput at this interval during each cycle (e.g. 100ms).
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    sample_interval: Option<std::time::Duration>,
+
+    /// Sample throughput every time this many bytes have been transferred during each cycle.
+    #[arg(long, value_name = "SIZE", value_parser = parse_data_size)]
+    sample_bytes: Option<DataSize<usize>>,
+
     /// Export the timing summary statistics and timings of individual runs as JSON to the given FILE. The output time unit is always seconds.
     #[arg(short('j'), long, value_name = "FILE")]
     export_json: Option<PathBuf>,
 
+    /// Export one row per cycle as CSV to the given FILE, appending if it already exists.
+    #[arg(long, value_name = "FILE")]
+    export_csv: Option<PathBuf>,
+
+    /// Export one row of statistics per run as CSV to the given FILE, appending if it already exists.
+    #[arg(long, value_name = "FILE")]
+    export_csv_summary: Option<PathBuf>,
+
+    /// Export a Markdown report to the given FILE.
+    #[arg(long, value_name = "FILE")]
+    export_markdown: Option<PathBuf>,
+
+    /// Export a self-contained HTML report with charts to the given FILE.
+    #[arg(long, value_name = "FILE")]
+    export_html: Option<PathBuf>,
+
+    /// Export throughput, IOPS and latency as OpenMetrics gauges to the given FILE (e.g. for node_exporter's textfile collector). The file is replaced atomically.
+    #[arg(long, value_name = "FILE")]
+    export_prometheus: Option<PathBuf>,
+
+    /// Export an equivalent fio job file to the given FILE, to reproduce the results with fio. Options that don't map to fio are listed at the top of the file.
+    #[arg(long, value_name = "FILE")]
+    export_fio: Option<PathBuf>,
+
+    /// Stream a record of every completed cycle and run to the given FILE (appending if it already exists) while the benchmark runs, or to stdout with `-` (the results for humans then go to stderr).
+    #[arg(long, value_name = "FILE")]
+    stream: Option<PathBuf>,
+
+    /// Format of the --stream records: one JSON object per line, or InfluxDB line protocol.
+    #[arg(long, value_enum, default_value_t = StreamFormatArg::Jsonl, requires = "stream")]
+    stream_format: StreamFormatArg,
+
+    /// Print the results using this minijinja template FILE instead of the built-in summary. See docs/templates.md.
+    #[arg(long, value_name = "FILE")]
+    template: Option<PathBuf>,
+
+    /// Render this minijinja template FILE to --output. See docs/templates.md.
+    #[arg(long, value_name = "FILE", requires = "output")]
+    output_template: Option<PathBuf>,
+
+    /// File to write the rendered --output-template to.
+    #[arg(long, value_name = "FILE", requires = "output_template")]
+    output: Option<PathBuf>,
+
+    /// Store the session, its runs and cycles in the SQLite database FILE, creating it if needed. See the `history` command.
+    #[arg(long, value_name = "FILE")]
+    db: Option<PathBuf>,
+
     /// Export the log to the given FILE.
     #[arg(long, value_name = "FILE")]
     export_log: Option<PathBuf>,
@@ -91,6 +222,63 @@ struct Args {
     verbose: Verbosity<WarnLevel>,
 }
 
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// List past sessions stored with --db and show how throughput changed over time.
+    History(HistoryArgs),
+    /// Print the JSON Schema of the reports written by --export-json.
+    Schema,
+    /// Import fio's JSON output (`fio --output-format=json`) as one session per job, to compare and report it like
+    /// the results of a benchmark.
+    ImportFio(ImportFioArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct ImportFioArgs {
+    /// The fio JSON output to import.
+    #[arg(value_name = "FILE")]
+    path: PathBuf,
+
+    /// Append the imported sessions to the JSON report FILE.
+    #[arg(short('j'), long, value_name = "FILE")]
+    export_json: Option<PathBuf>,
+
+    /// Append one row of statistics per imported run as CSV to the given FILE.
+    #[arg(long, value_name = "FILE")]
+    export_csv_summary: Option<PathBuf>,
+
+    /// Store the imported sessions in the SQLite database FILE, see the `history` command.
+    #[arg(long, value_name = "FILE")]
+    db: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct HistoryArgs {
+    /// The SQLite database to read.
+    #[arg(long, value_name = "FILE")]
+    db: PathBuf,
+
+    /// Only show sessions run on this host.
+    #[arg(long)]
+    host: Option<String>,
+
+    /// Only show sessions that used this test file. `%` matches any characters, e.g. `/Volumes/%`.
+    #[arg(long, value_name = "FILE")]
+    path: Option<String>,
+
+    /// Only show runs of this mode.
+    #[arg(short, long, value_enum)]
+    mode: Option<Mode>,
+
+    /// Only show sessions since this date (e.g. 2024-01-31 or "2024-01-31 12:00:00", UTC) or for this long (e.g. 30days).
+    #[arg(long, value_name = "DATE", value_parser = parse_time)]
+    since: Option<SystemTime>,
+
+    /// Only show sessions before this date (e.g. 2024-01-31 or "2024-01-31 12:00:00", UTC) or this long ago (e.g. 7days).
+    #[arg(long, value_name = "DATE", value_parser = parse_time)]
+    until: Option<SystemTime>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Display, clap::ValueEnum)]
 enum Mode {
     All,
@@ -98,6 +286,95 @@ enum Mode {
     Write,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Chart {
+    Elapsed,
+    Throughput,
+    Latency,
+}
+
+// The library's enums don't depend on clap, these mirror them for the command line.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum EngineArg {
+    /// `read(2)`/`write(2)` on the file descriptor.
+    Syscall,
+    /// Copy in and out of a shared memory mapping of the file.
+    Mmap,
+}
+
+impl From<EngineArg> for Engine {
+    fn from(engine: EngineArg) -> Self {
+        match engine {
+            EngineArg::Syscall => Engine::Syscall,
+            EngineArg::Mmap => Engine::Mmap,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum AdviceArg {
+    Normal,
+    Sequential,
+    Random,
+    WillNeed,
+    DontNeed,
+}
+
+impl From<AdviceArg> for Advice {
+    fn from(advice: AdviceArg) -> Self {
+        match advice {
+            AdviceArg::Normal => Advice::Normal,
+            AdviceArg::Sequential => Advice::Sequential,
+            AdviceArg::Random => Advice::Random,
+            AdviceArg::WillNeed => Advice::WillNeed,
+            AdviceArg::DontNeed => Advice::DontNeed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum StreamFormatArg {
+    /// One JSON object per line.
+    Jsonl,
+    /// InfluxDB line protocol.
+    Influx,
+}
+
+impl From<StreamFormatArg> for StreamFormat {
+    fn from(format: StreamFormatArg) -> Self {
+        match format {
+            StreamFormatArg::Jsonl => StreamFormat::Jsonl,
+            StreamFormatArg::Influx => StreamFormat::Influx,
+        }
+    }
+}
+
+/// Set when `--stream -` writes records to stdout, the results for humans go to stderr then.
+static STREAM_TO_STDOUT: AtomicBool = AtomicBool::new(false);
+
+/// Like `print!`, but to stderr when stdout is streamed to.
+macro_rules! out {
+    ($($arg:tt)*) => {
+        if STREAM_TO_STDOUT.load(Ordering::Relaxed) {
+            eprint!($($arg)*)
+        } else {
+            print!($($arg)*)
+        }
+    };
+}
+
+/// Like `println!`, but to stderr when stdout is streamed to.
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        if STREAM_TO_STDOUT.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
@@ -111,34 +388,37 @@ fn main() -> Result<()> {
 
     log::debug!("{:?}", args);
 
-    let file_size: usize = args.file_size.into();
-    let block_size: usize = args.block_size.into();
-    ensure!(
-        file_size > block_size,
-        "File size ({}) is smaller than block size ({}).",
-        args.file_size,
-        args.block_size
-    );
-    ensure!(
-        args.cycles >= 2,
-        "Number of cycles must be at least two. (`--cycles 2`)"
-    );
-    ensure!(file_size > 0, "File size must be greater than zero.");
-    ensure!(block_size > 0, "Block size must be greater than zero.");
-
-    // if file size is not divisible by block size, reduce file size and log a warning
-    if file_size % block_size != 0 {
-        let new_file_size = file_size - (file_size % block_size);
-        log::warn!(
-            "File size ({}) is not divisible by block size ({}). Reducing file size to {}.",
-            args.file_size,
-            args.block_size,
-            DataSize::from(new_file_size),
-        );
+    match &args.command {
+        Some(Command::History(history)) => return display_history(history),
+        Some(Command::ImportFio(import)) => return import_fio_sessions(import),
+        Some(Command::Schema) => {
+            println!("{}", serde_json::to_string_pretty(&report_schema())?);
+            return Ok(());
+        }
+        None => {}
     }
 
+    // Load the templates first, a mistake in one shouldn't waste a benchmark.
+    let report_template = args
+        .template
+        .as_deref()
+        .map(ReportTemplate::load)
+        .transpose()?;
+    let output_template = args
+        .output_template
+        .as_deref()
+        .map(ReportTemplate::load)
+        .transpose()?;
+
+    STREAM_TO_STDOUT.store(
+        args.stream.as_deref() == Some(std::path::Path::new("-")),
+        Ordering::Relaxed,
+    );
+
     let modes: HashSet<&Mode> = HashSet::from_iter(args.mode.iter());
-    let modes = if modes.contains(&Mode::All) {
+    let modes = if args.sustained {
+        vec![Mode::Write]
+    } else if modes.contains(&Mode::All) {
         vec![Mode::Read, Mode::Write]
     } else {
         args.mode.clone()
@@ -153,122 +433,619 @@ fn main() -> Result<()> {
         })
         .collect::<Vec<ReadWrite>>();
 
+    let cycles = if args.sustained { 1 } else { args.cycles };
+
+    let chart = if args.sustained {
+        Chart::Throughput
+    } else {
+        args.chart
+    };
+    let mut sample_interval = args.sample_interval;
+    if sample_interval.is_none() && args.sample_bytes.is_none() {
+        if args.sustained {
+            sample_interval = Some(std::time::Duration::from_secs(1));
+        } else if chart == Chart::Throughput {
+            sample_interval = Some(std::time::Duration::from_millis(100));
+        }
+    }
+    let sustained = args.sustained.then(|| SustainedOptions {
+        bytes: args.sustained_bytes.map(|b| b.into()),
+        duration: args.sustained_duration.map(|d| d.as_secs_f64()),
+    });
+    let adaptive = args.adaptive.then_some(AdaptiveOptions {
+        max_cycles: args.max_cycles,
+        window: args.steady_window,
+        target_rse: args.target_rse,
+    });
+
+    let dashboard = args.dashboard && std::io::stdout().is_terminal();
+    if args.dashboard && !dashboard {
+        log::warn!(
+            "Standard output is not a terminal, showing the progress bar instead of the dashboard."
+        );
+    }
+
+    let mut builder = SessionOptions::builder(args.path)
+        .modes(modes)
+        .file_size(args.file_size.into())
+        .block_size(args.block_size.into())
+        .cycles(cycles as usize)
+        .no_create(args.no_create)
+        .no_delete(args.no_delete)
+        .dry_run(args.dry_run)
+        .progress(!args.no_progress)
+        .dashboard(dashboard)
+        .cache(if args.no_disable_cache {
+            CachePolicy::Buffered
+        } else {
+            CachePolicy::Direct
+        })
+        .compare_cache(args.compare_cache)
+        .engine(args.engine.into())
+        .msync(!args.no_msync)
+        .pattern(args.pattern)
+        .no_close_file(args.no_close_file)
+        .no_random_buffer(args.no_random_buffer);
+    if args.random_seek {
+        builder = builder.random(args.distribution);
+    }
+    if let Some(advice) = args.madvise {
+        builder = builder.madvise(advice.into());
+    }
+    if let Some(block_sizes) = args.block_sizes {
+        builder = builder.block_sizes(block_sizes);
+    }
+    if let Some(rate_iops) = args.rate_iops {
+        builder = builder.rate_iops(rate_iops);
+    }
+    if let Some(rate_bytes) = args.rate_bytes {
+        builder = builder.rate_bytes(rate_bytes.into());
+    }
+    if let Some(interval) = sample_interval {
+        builder = builder.sample_interval(interval);
+    }
+    if let Some(bytes) = args.sample_bytes {
+        builder = builder.sample_bytes(bytes.into());
+    }
+    if let Some(sustained) = sustained {
+        builder = builder.sustained(sustained);
+    }
+    if let Some(adaptive) = adaptive {
+        builder = builder.adaptive(adaptive);
+    }
+    if let Some(path) = args.stream {
+        builder = builder.stream(StreamOptions {
+            path,
+            format: args.stream_format.into(),
+        });
+    }
+    let options = builder.build().unwrap_or_else(|error| {
+        log::error!("{}", error);
+        std::process::exit(error.exit_code());
+    });
     let info = os_info::get();
     info.version();
 
     let template = "File: <info>{{file}}</info>
 OS: <info>{{os.os_type}} {{os_version}} ({{os.architecture}})</info>
-Cycles: <num>{{ cycles }}</num>
+Engine: <info>{{ engine }}</info>
+Access: <info>{% if random_seek %}random ({{ distribution }}){% else %}{{ pattern }}{% endif %}</info>
+Cycles: {% if adaptive %}adaptive, up to <num>{{ max_cycles }}</num>{% else %}<num>{{ cycles }}</num>{% endif %}
 Block Size: <size>{{ block_size }}</size>
-File Size: <size>{{ file_size }}</size>";
+File Size: <size>{{ file_size }}</size>{% if rate_iops %}
+IOPS Limit: <num>{{ rate_iops }}</num>/sec{% endif %}{% if rate_bytes %}
+Bandwidth Limit: <speed>{{ rate_bytes }}</speed>/sec{% endif %}{% if sustained %}
+Sustained Write: {% if sustained_bytes %}<size>{{ sustained_bytes }}</size>{% endif %}{% if sustained_bytes and sustained_duration %} or {% endif %}{% if sustained_duration %}<num>{{ sustained_duration }}</num>{% endif %}{% endif %}";
     let context = context! {
-        file => args.path.to_string_lossy(),
+        file => options.path.to_string_lossy(),
         os => info,
         os_version => info.version().to_string(),
-        cycles => args.cycles,
-        block_size => args.block_size.to_human_string(),
-        file_size => args.file_size.to_human_string(),
+        engine => options.engine.to_string(),
+        random_seek => options.random_seek,
+        distribution => options.distribution.to_string(),
+        pattern => options.pattern.to_string(),
+        cycles => cycles,
+        adaptive => args.adaptive,
+        max_cycles => args.max_cycles,
+        block_size => match &options.block_sizes {
+            Some(block_sizes) => block_sizes.to_string(),
+            None => args.block_size.to_human_string(),
+        },
+        file_size => DataSize::from(options.file_size).to_human_string(),
+        rate_iops => args.rate_iops,
+        rate_bytes => args.rate_bytes.map(|r| r.to_human_string()),
+        sustained => args.sustained,
+        sustained_bytes => args.sustained_bytes.map(|b| b.to_human_string()),
+        sustained_duration => args.sustained_duration.map(|d| humantime::format_duration(d).to_string()),
     };
     render(template, &context)?;
 
-    // TODO: It's rather silly copying all this from Args.
-    let options = SessionOptions {
-        modes,
-        path: args.path,
-        file_size: args.file_size.into(),
-        block_size: args.block_size.into(),
-        cycles: args.cycles as usize,
-        no_create: args.no_create,
-        no_delete: args.no_delete,
-        dry_run: args.dry_run,
-        no_progress: args.no_progress,
-        no_disable_cache: args.no_disable_cache,
-        random_seek: args.random_seek,
-        no_close_file: args.no_close_file,
-        no_random_buffer: args.no_random_buffer,
-    };
-    let session = Session { options };
-    let session_result = session.main().expect("Session failed.");
+    let session = Session::new(options);
+    let interrupted = session.interrupt_flag();
+    ctrlc::set_handler(move || {
+        if interrupted.swap(true, Ordering::Relaxed) {
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        eprintln!("Interrupted, stopping after the current operation. Press Ctrl-C again to quit immediately.");
+    })?;
+    // Logging would draw over the dashboard, show the messages once it's gone.
+    let dashboard = session.options.dashboard;
+    if dashboard {
+        *HELD_LOGS.lock().unwrap() = Some(Vec::new());
+    }
+    let session_result = session.main();
+    if dashboard {
+        for line in HELD_LOGS.lock().unwrap().take().unwrap_or_default() {
+            eprintln!("{}", line);
+        }
+    }
+    let session_result = session_result.unwrap_or_else(|error| {
+        log::error!("{}", error);
+        std::process::exit(error.exit_code());
+    });
 
-    for run_result in session_result.runs.iter() {
-        run_result.display_result();
+    if let Some(template) = &report_template {
+        let style_sheet = StyleSheet::parse(STYLE_SHEET).expect("Failed to parse stylesheet.");
+        let output = template.render(&session_result)?;
+        outln!("{}", style_sheet.render(&output)?);
+    } else {
+        for run_result in session_result.runs.iter() {
+            run_result.display_result()?;
+        }
+
+        if args.compare_cache {
+            display_cache_comparison(&session_result.runs)?;
+        }
     }
 
     if !args.no_chart {
-        let data: Vec<Vec<f64>> = session_result
-            .runs
-            .iter()
-            .map(|r| r.cycle_results.iter().map(|c| c.elapsed).collect())
-            .collect();
-        let res = rasciigraph::plot_many(
-            data,
-            rasciigraph::Config::default()
-                .with_height(10)
-                .with_width(80),
-        );
-        print!("Timing:\n{}", res);
+        match chart {
+            Chart::Elapsed => {
+                let data: Vec<Vec<f64>> = session_result
+                    .runs
+                    .iter()
+                    .map(|r| r.cycle_results.iter().map(|c| c.elapsed).collect())
+                    .collect();
+                let res = rasciigraph::plot_many(
+                    data,
+                    rasciigraph::Config::default()
+                        .with_height(10)
+                        .with_width(80),
+                );
+                out!("Timing:\n{}", res);
+            }
+            Chart::Throughput => {
+                // Throughput in MB/s of each sample, cycles one after the other.
+                let data: Vec<Vec<f64>> = session_result
+                    .runs
+                    .iter()
+                    .map(|r| {
+                        r.cycle_results
+                            .iter()
+                            .flat_map(|c| c.samples.iter())
+                            .map(|s| s.throughput() / Unit::MB.bytes() as f64)
+                            .collect()
+                    })
+                    .collect();
+                let res = rasciigraph::plot_many(
+                    data,
+                    rasciigraph::Config::default()
+                        .with_height(10)
+                        .with_width(80),
+                );
+                out!("Throughput (MB/sec):\n{}", res);
+            }
+            Chart::Latency => {
+                for run_result in session_result.runs.iter() {
+                    display_latency_chart(run_result)?;
+                }
+            }
+        }
     }
 
-    if let Some(path) = args.export_json {
-        if path.exists() {
-            log::warn!("File {} already exists, appending.", path.display());
-            let mut file = File::open(&path)?;
-            let mut reports: Vec<SessionResult> = serde_json::from_reader(&mut file)?;
-            reports.push(session_result);
-            let file = File::create(path)?;
-            serde_json::to_writer_pretty(file, &reports)?;
-        } else {
-            let reports = vec![session_result];
-            let file = File::create(path)?;
-            serde_json::to_writer_pretty(file, &reports)?;
-        }
+    if let Some(path) = &args.export_csv {
+        export_cycles_csv(path, &session_result)?;
+    }
+    if let Some(path) = &args.export_csv_summary {
+        export_summary_csv(path, &session_result)?;
+    }
+
+    if let Some(path) = &args.export_markdown {
+        export_markdown(path, &session_result)?;
+    }
+    if let Some(path) = &args.export_html {
+        export_html(path, &session_result)?;
+    }
+
+    if let Some(path) = &args.export_prometheus {
+        export_prometheus(path, &session_result)?;
+    }
+
+    if let Some(path) = &args.export_fio {
+        export_fio(path, &session_result)?;
+    }
+
+    if let (Some(template), Some(path)) = (&output_template, &args.output) {
+        std::fs::write(path, template.render(&session_result)?)?;
+    }
+
+    if let Some(path) = &args.db {
+        Database::open(path)?.insert(&session_result)?;
+    }
+
+    if let Some(path) = &args.export_json {
+        export_json(path, &session_result)?;
+    }
+
+    if session_result.interrupted {
+        log::warn!("The benchmark was interrupted, the results only include completed cycles.");
+        std::process::exit(EXIT_INTERRUPTED);
+    }
+    if let Some(failure) = session_result.failures.first() {
+        log::warn!(
+            "{} of {} runs failed.",
+            session_result.failures.len(),
+            session_result.failures.len() + session_result.runs.len()
+        );
+        std::process::exit(failure.kind.exit_code());
     }
 
     Ok(())
 }
 
+/// Exit status after SIGINT or SIGTERM, as shells report a process killed by SIGINT.
+const EXIT_INTERRUPTED: i32 = 130;
+
 trait RunDisplay {
-    fn display_result(&self);
+    fn display_result(&self) -> Result<()>;
 }
 
 impl RunDisplay for RunResult {
-    fn display_result(&self) {
-        let template = "Mode: <mode>{{mode}}</mode>
+    fn display_result(&self) -> Result<()> {
+        let template = "Mode: <mode>{{mode}}</mode>, Cache: <info>{{cache}}</info>
 Mean: <speed>{{mean}}</speed>/sec, Median: <speed>{{median}}</speed>/sec, Standard Deviation Ø: <speed>{{standard_deviation}}</speed>/sec
 Min: <speed>{{min}}</speed>/sec, Max: <speed>{{max}}</speed>/sec";
         let context = context! {
             mode => self.mode.to_string(),
+            cache => self.cache.to_string(),
             mean => DataSize::from(self.statistics.mean).to_human_string(),
             median => DataSize::from(self.statistics.median).to_human_string(),
             standard_deviation => DataSize::from(self.statistics.standard_deviation).to_human_string(),
             min => DataSize::from(self.statistics.min).to_human_string(),
             max => DataSize::from(self.statistics.max).to_human_string(),
         };
-        render(template, &context).unwrap();
+        render(template, &context)?;
+
+        let template = "Total: <speed>{{total}}</speed>/sec, IOPS: <num>{{iops}}</num>, Harmonic Mean: <speed>{{harmonic_mean}}</speed>/sec, Coefficient of Variation: <num>{{cv}}</num>{% if mean_interval %}
+{{confidence}} CI: Mean <speed>{{mean_interval[0]}}</speed>/sec – <speed>{{mean_interval[1]}}</speed>/sec, Median <speed>{{median_interval[0]}}</speed>/sec – <speed>{{median_interval[1]}}</speed>/sec{% endif %}{% if outliers %}
+Outlier Cycles: {% for cycle in outliers %}<num>{{cycle}}</num>{% if not loop.last %}, {% endif %}{% endfor %}{% endif %}";
+        let interval = |interval: &Option<ConfidenceInterval>| {
+            interval.as_ref().map(|i| {
+                vec![
+                    DataSize::from(i.lower).to_human_string(),
+                    DataSize::from(i.upper).to_human_string(),
+                ]
+            })
+        };
+        let context = context! {
+            total => DataSize::from(self.statistics.total_throughput).to_human_string(),
+            iops => format!("{:.0}", self.statistics.iops),
+            harmonic_mean => DataSize::from(self.statistics.harmonic_mean).to_human_string(),
+            cv => format!("{:.1}%", self.statistics.coefficient_of_variation * 100.0),
+            confidence => self.statistics.mean_interval.as_ref().map(|i| format!("{:.0}%", i.confidence * 100.0)),
+            mean_interval => interval(&self.statistics.mean_interval),
+            median_interval => interval(&self.statistics.median_interval),
+            outliers => self.statistics.outliers.iter().map(|i| i + 1).collect::<Vec<_>>(),
+        };
+        render(template, &context)?;
+
+        if let Some(latency) = &self.statistics.latency {
+            let template = "Latency: Mean: <num>{{mean}}</num>, p50: <num>{{p50}}</num>, p90: <num>{{p90}}</num>, p99: <num>{{p99}}</num>, p99.9: <num>{{p999}}</num>, Max: <num>{{max}}</num>";
+            let context = context! {
+                mean => format_latency(latency.mean),
+                p50 => format_latency(latency.p50),
+                p90 => format_latency(latency.p90),
+                p99 => format_latency(latency.p99),
+                p999 => format_latency(latency.p999),
+                max => format_latency(latency.max),
+            };
+            render(template, &context)?;
+        }
+
+        if !self.statistics.block_sizes.is_empty() {
+            let template = "{% for b in block_sizes %}Block Size: <size>{{b.block_size}}</size>, Ops: <num>{{b.ops}}</num>, Throughput: <speed>{{b.throughput}}</speed>/sec, Mean Latency: <num>{{b.mean_latency}}</num>
+{% endfor %}All: Ops: <num>{{ops}}</num>, Mean Latency: <num>{{mean_latency}}</num>";
+            let block_sizes: Vec<_> = self
+                .statistics
+                .block_sizes
+                .iter()
+                .map(|b| {
+                    context! {
+                        block_size => DataSize::from(b.block_size).to_human_string(),
+                        ops => b.ops,
+                        throughput => DataSize::from(b.throughput).to_human_string(),
+                        mean_latency => format_latency(b.mean_latency),
+                    }
+                })
+                .collect();
+            let ops: usize = self.statistics.block_sizes.iter().map(|b| b.ops).sum();
+            let elapsed: f64 = self
+                .statistics
+                .block_sizes
+                .iter()
+                .map(|b| b.mean_latency * b.ops as f64)
+                .sum();
+            let context = context! {
+                block_sizes,
+                ops,
+                mean_latency => format_latency(elapsed / ops as f64),
+            };
+            render(template, &context)?;
+        }
+
+        if let Some(sustained) = &self.sustained {
+            let template = "Burst: <speed>{{burst}}</speed>/sec, Steady State: <speed>{{steady}}</speed>/sec
+{% if knee_bytes %}Throughput dropped after <size>{{knee_bytes}}</size> written (<num>{{knee_time}}</num>){% else %}No throughput drop detected{% endif %}";
+            let context = context! {
+                burst => DataSize::from(sustained.burst_throughput).to_human_string(),
+                steady => DataSize::from(sustained.steady_throughput).to_human_string(),
+                knee_bytes => sustained.knee.as_ref().map(|k| DataSize::from(k.bytes).to_human_string()),
+                knee_time => sustained.knee.as_ref().map(|k| format!("{:.1}s", k.time)),
+            };
+            render(template, &context)?;
+        }
+
+        if let Some(convergence) = &self.convergence {
+            let template = "{% if converged %}Converged after <num>{{cycles}}</num> cycles{% else %}Did not converge after <num>{{cycles}}</num> cycles{% endif %}, Steady State: <info>{{steady_state}}</info>, Relative Standard Error: <num>{{rse}}</num>";
+            let context = context! {
+                converged => convergence.converged,
+                cycles => convergence.cycles,
+                steady_state => if convergence.steady_state { "yes" } else { "no" },
+                rse => format!("{:.2}%", convergence.relative_standard_error * 100.0),
+            };
+            render(template, &context)?;
+        }
+
+        let page_faults: Vec<&PageFaults> = self
+            .cycle_results
+            .iter()
+            .filter_map(|c| c.page_faults.as_ref())
+            .collect();
+        if !page_faults.is_empty() {
+            let template = "Page Faults: <num>{{minor}}</num> minor, <num>{{major}}</num> major";
+            let context = context! {
+                minor => page_faults.iter().map(|p| p.minor).sum::<u64>(),
+                major => page_faults.iter().map(|p| p.major).sum::<u64>(),
+            };
+            render(template, &context)?;
+        }
+        Ok(())
     }
 }
 
+fn display_cache_comparison(runs: &[RunResult]) -> Result<()> {
+    let template = "Mode: <mode>{{mode}}</mode>, Direct: <speed>{{direct}}</speed>/sec, Buffered: <speed>{{buffered}}</speed>/sec, Ratio: <num>{{ratio}}</num>x";
+    for direct in runs.iter().filter(|r| r.cache == CachePolicy::Direct) {
+        let Some(buffered) = runs
+            .iter()
+            .find(|r| r.mode == direct.mode && r.cache == CachePolicy::Buffered)
+        else {
+            continue;
+        };
+        let context = context! {
+            mode => direct.mode.to_string(),
+            direct => DataSize::from(direct.statistics.mean).to_human_string(),
+            buffered => DataSize::from(buffered.statistics.mean).to_human_string(),
+            ratio => format!("{:.2}", buffered.statistics.mean / direct.statistics.mean),
+        };
+        render(template, &context)?;
+    }
+    Ok(())
+}
+
+fn import_fio_sessions(args: &ImportFioArgs) -> Result<()> {
+    let sessions = import_fio(&args.path)?;
+    let mut database = args.db.as_deref().map(Database::open).transpose()?;
+    let template = "Imported: <info>{{args}}</info>, Source: <info>{{source}}</info>
+File: <info>{{file}}</info>, Access: <info>{% if random_seek %}random{% else %}sequential{% endif %}</info>, Block Size: <size>{{block_size}}</size>, File Size: <size>{{file_size}}</size>";
+    for session in sessions.iter() {
+        let context = context! {
+            args => session.args,
+            source => session.source,
+            file => session.options.path.to_string_lossy(),
+            random_seek => session.options.random_seek,
+            block_size => DataSize::from(session.options.block_size).to_human_string(),
+            file_size => DataSize::from(session.options.file_size).to_human_string(),
+        };
+        render(template, &context)?;
+        for run_result in session.runs.iter() {
+            run_result.display_result()?;
+        }
+
+        if let Some(path) = &args.export_json {
+            export_json(path, session)?;
+        }
+        if let Some(path) = &args.export_csv_summary {
+            export_summary_csv(path, session)?;
+        }
+        if let Some(database) = &mut database {
+            database.insert(session)?;
+        }
+    }
+    Ok(())
+}
+
+fn display_history(args: &HistoryArgs) -> Result<()> {
+    ensure!(
+        args.db.exists(),
+        "Database {} does not exist.",
+        args.db.display()
+    );
+    let database = Database::open(&args.db)?;
+    let rows = database.history(&HistoryFilter {
+        host: args.host.clone(),
+        path: args.path.clone(),
+        mode: match args.mode {
+            Some(Mode::Read) => Some(ReadWrite::Read),
+            Some(Mode::Write) => Some(ReadWrite::Write),
+            _ => None,
+        },
+        since: args.since,
+        until: args.until,
+    })?;
+    if rows.is_empty() {
+        println!("No matching sessions.");
+        return Ok(());
+    }
+    let created = |row: &HistoryRow| humantime::format_rfc3339_seconds(row.created).to_string();
+    let host = |row: &HistoryRow| row.host.clone().unwrap_or("unknown".to_string());
+
+    let template = "Session: <num>{{id}}</num>, Created: <info>{{created}}</info>, Host: <info>{{host}}</info>, File: <info>{{path}}</info>{% for run in runs %}
+  Mode: <mode>{{run.mode}}</mode>, Cache: <info>{{run.cache}}</info>, Block Size: <size>{{run.block_size}}</size>, Cycles: <num>{{run.cycles}}</num>, Total: <speed>{{run.total}}</speed>/sec, IOPS: <num>{{run.iops}}</num>{% endfor %}";
+    for session in rows.chunk_by(|a, b| a.session_id == b.session_id) {
+        let runs: Vec<_> = session
+            .iter()
+            .map(|row| {
+                context! {
+                    mode => row.mode,
+                    cache => row.cache,
+                    block_size => DataSize::from(row.block_size).to_human_string(),
+                    cycles => row.cycles,
+                    total => DataSize::from(row.total_throughput).to_human_string(),
+                    iops => format!("{:.0}", row.iops),
+                }
+            })
+            .collect();
+        let context = context! {
+            id => session[0].session_id,
+            created => created(&session[0]),
+            host => host(&session[0]),
+            path => session[0].path,
+            runs,
+        };
+        render(template, &context)?;
+    }
+
+    // One trend per combination of host, file, mode, cache policy and block size.
+    let mut trends: Vec<Vec<&HistoryRow>> = Vec::new();
+    for row in rows.iter() {
+        let key = |r: &HistoryRow| {
+            (
+                r.host.clone(),
+                r.path.clone(),
+                r.mode.clone(),
+                r.cache.clone(),
+                r.block_size,
+            )
+        };
+        match trends.iter_mut().find(|t| key(t[0]) == key(row)) {
+            Some(trend) => trend.push(row),
+            None => trends.push(vec![row]),
+        }
+    }
+    let template = "
+Trend: <mode>{{mode}}</mode> ({{cache}}), Host: <info>{{host}}</info>, File: <info>{{path}}</info>, Block Size: <size>{{block_size}}</size> {{sparkline}}
+Created               Total            Change  Mean ± Standard Deviation       IOPS  p99 Latency{% for row in rows %}
+{{row.created}}  <speed>{{row.total}}</speed>/sec  <num>{{row.change}}</num>  <speed>{{row.mean}}</speed> ± <speed>{{row.standard_deviation}}</speed>/sec  <num>{{row.iops}}</num>  <num>{{row.latency}}</num>{% endfor %}";
+    for trend in trends.iter() {
+        let throughputs: Vec<f64> = trend.iter().map(|r| r.total_throughput).collect();
+        let rows: Vec<_> = trend
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let change = (index > 0).then(|| {
+                    let previous = throughputs[index - 1];
+                    (row.total_throughput - previous) / previous * 100.0
+                });
+                context! {
+                    created => created(row),
+                    total => format!("{:>10}", DataSize::from(row.total_throughput).to_human_string()),
+                    change => format!("{:>8}", change.map_or(String::new(), |c| format!("{:+.1}%", c))),
+                    mean => format!("{:>10}", DataSize::from(row.mean).to_human_string()),
+                    standard_deviation => format!("{:>10}", DataSize::from(row.standard_deviation).to_human_string()),
+                    iops => format!("{:>9.0}", row.iops),
+                    latency => format!("{:>11}", row.latency_p99.map_or(String::new(), format_latency)),
+                }
+            })
+            .collect();
+        let context = context! {
+            mode => trend[0].mode,
+            cache => trend[0].cache,
+            host => host(trend[0]),
+            path => trend[0].path,
+            block_size => DataSize::from(trend[0].block_size).to_human_string(),
+            sparkline => sparkline(&throughputs),
+            rows,
+        };
+        render(template, &context)?;
+    }
+    Ok(())
+}
+
+fn display_latency_chart(run: &RunResult) -> Result<()> {
+    let mut histogram = LatencyHistogram::default();
+    for cycle_result in run.cycle_results.iter() {
+        histogram.merge(&cycle_result.latencies);
+    }
+    if histogram.is_empty() {
+        return Ok(());
+    }
+
+    let bins = latency_bins(&histogram, 16);
+    let most = bins.iter().map(|b| b.count).max().unwrap_or(0);
+    let total: u64 = bins.iter().map(|b| b.count).sum();
+    let template = "Latency Histogram: <mode>{{mode}}</mode>, Cache: <info>{{cache}}</info>{% for b in bins %}
+<num>{{b.lower}}</num> – <num>{{b.upper}}</num> │<speed>{{b.bar}}</speed> <num>{{b.count}}</num> ({{b.percent}}){% endfor %}";
+    let bins: Vec<_> = bins
+        .iter()
+        .map(|b| {
+            context! {
+                lower => format!("{:>9}", format_latency(b.lower)),
+                upper => format!("{:>9}", format_latency(b.upper)),
+                bar => bar(b.count, most, 50),
+                count => b.count,
+                percent => format!("{:.1}%", b.count as f64 / total as f64 * 100.0),
+            }
+        })
+        .collect();
+    let context = context! {
+        mode => run.mode.to_string(),
+        cache => run.cache.to_string(),
+        bins,
+    };
+    render(template, &context)?;
+
+    let (latencies, percentages) = latency_cdf(&histogram, 80);
+    let res = rasciigraph::plot(
+        percentages,
+        rasciigraph::Config::default()
+            .with_height(10)
+            .with_width(80),
+    );
+    outln!("Latency CDF (%):\n{}", res);
+    let template = "<num>{{min}}</num> to <num>{{max}}</num> (log scale)";
+    let context = context! {
+        min => format_latency(latencies[0]),
+        max => format_latency(latencies[latencies.len() - 1]),
+    };
+    render(template, &context)
+}
+
 fn render(template: &str, context: &minijinja::value::Value) -> Result<()> {
-    let style_sheet = StyleSheet::parse(
-        "
-        info { foreground: yellow }
-        mode { foreground: red }
-        speed { foreground: cyan }
-        size { foreground: green }
-        num { foreground: yellow }
-        ",
-    )
-    .expect("Failed to parse stylesheet.");
+    let style_sheet = StyleSheet::parse(STYLE_SHEET).expect("Failed to parse stylesheet.");
 
     let mut env = Environment::new();
-    env.add_template("template", template).unwrap();
-    let tmpl = env.get_template("template").unwrap();
-    let render = tmpl.render(context).unwrap();
-    println!("{}", style_sheet.render(&render)?);
+    env.add_template("template", template)?;
+    let tmpl = env.get_template("template")?;
+    let render = tmpl.render(context)?;
+    outln!("{}", style_sheet.render(&render)?);
 
     Ok(())
 }
 
+/// Console log lines held back while the dashboard is shown.
+static HELD_LOGS: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
 fn setup_logger(
     level_filter: log::LevelFilter,
     log_path: &Option<PathBuf>,
@@ -278,6 +1055,7 @@ fn setup_logger(
         .info(Color::Green)
         .debug(Color::Magenta);
     let mut base_logger = fern::Dispatch::new();
+    // Log to stderr, stdout belongs to the results and the dashboard.
     let console_logger = fern::Dispatch::new()
         .level(level_filter)
         .format(move |out, message, record| {
@@ -291,7 +1069,12 @@ fn setup_logger(
                 message
             ))
         })
-        .chain(std::io::stdout());
+        .chain(fern::Output::call(|record| {
+            match HELD_LOGS.lock().unwrap().as_mut() {
+                Some(lines) => lines.push(record.args().to_string()),
+                None => eprintln!("{}", record.args()),
+            }
+        }));
     base_logger = base_logger.chain(console_logger);
 
     if let Some(log_path) = log_path {
@@ -333,4 +1116,22 @@ mod tests {
             .unwrap();
         println!("{:?}", output);
     }
+
+    #[test]
+    fn test_stream_to_stdout() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args(["--size", "1MB", "--blocksize", "64KB", "--stream", "-"])
+            .arg(tmp_dir.path().join("test.dat"))
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let records: Vec<serde_json::Value> = stdout
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // 10 cycles and a run for each mode.
+        assert_eq!(records.len(), 2 * 11);
+        assert!(!output.stderr.is_empty());
+    }
 }
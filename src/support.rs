@@ -17,7 +17,7 @@ pub enum Unit {
 
 impl Unit {
     // TODO: Really need a generic version.
-    fn bytes(&self) -> u64 {
+    pub fn bytes(&self) -> u64 {
         match self {
             Unit::B => 1,
             Unit::KB => 1024,
@@ -192,3 +192,60 @@ pub fn min(vals: &[f64]) -> f64 {
         .min_by(|a, b| a.partial_cmp(b).unwrap())
         .unwrap()
 }
+
+/// Styles for the markup used in console output.
+pub const STYLE_SHEET: &str = "
+    info { foreground: yellow }
+    mode { foreground: red }
+    speed { foreground: cyan }
+    size { foreground: green }
+    num { foreground: yellow }
+    ";
+
+/// Formats a duration in seconds with a unit suited to its magnitude, e.g. `12.3 µs`.
+pub fn format_latency(seconds: f64) -> String {
+    if seconds >= 1.0 {
+        format!("{:.2} s", seconds)
+    } else if seconds >= 1e-3 {
+        format!("{:.2} ms", seconds * 1e3)
+    } else if seconds >= 1e-6 {
+        format!("{:.2} µs", seconds * 1e6)
+    } else {
+        format!("{:.0} ns", seconds * 1e9)
+    }
+}
+
+#[test]
+fn test_format_latency() {
+    assert_eq!(format_latency(2.5), "2.50 s");
+    assert_eq!(format_latency(0.0125), "12.50 ms");
+    assert_eq!(format_latency(0.0000123), "12.30 µs");
+    assert_eq!(format_latency(0.000000042), "42 ns");
+}
+
+/// Parses a date (`2024-01-31`), a date and time in UTC (`2024-01-31 12:00:00`) or a duration (`7days`), which means
+/// that long ago.
+pub fn parse_time(s: &str) -> Result<std::time::SystemTime> {
+    if let Ok(duration) = humantime::parse_duration(s) {
+        return Ok(std::time::SystemTime::now() - duration);
+    }
+    humantime::parse_rfc3339_weak(s)
+        .or_else(|_| humantime::parse_rfc3339_weak(&format!("{} 00:00:00", s)))
+        .map_err(|_| anyhow!("Invalid date, time or duration"))
+}
+
+#[test]
+fn test_parse_time() {
+    let time = parse_time("2024-01-31").unwrap();
+    assert_eq!(
+        humantime::format_rfc3339_seconds(time).to_string(),
+        "2024-01-31T00:00:00Z"
+    );
+    let time = parse_time("2024-01-31T12:30:00").unwrap();
+    assert_eq!(
+        humantime::format_rfc3339_seconds(time).to_string(),
+        "2024-01-31T12:30:00Z"
+    );
+    assert!(parse_time("1day").unwrap() < std::time::SystemTime::now());
+    assert!(parse_time("yesterday").is_err());
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[cfg(target_os = "macos")]
+#[cfg(any(target_os = "macos", target_os = "linux"))]
 use std::path::Path;
 
 #[cfg(target_os = "macos")]
@@ -45,6 +45,10 @@ struct SystemProfile {
 
 #[cfg(target_os = "macos")]
 impl Volume {
+    pub fn device(&self) -> Option<&str> {
+        Some(&self.bsd_name)
+    }
+
     pub fn volume_for_path(path: &Path) -> anyhow::Result<Self> {
         if !path.exists() {
             return Err(anyhow::anyhow!("Path {} does not exist", path.display()));
@@ -97,12 +101,40 @@ impl StatFSStuff for libc::statfs {
 
 #[cfg(target_os = "linux")]
 #[derive(Serialize, Deserialize, Debug)]
-pub struct Volume {}
+pub struct Volume {
+    device: String,
+    mount_point: PathBuf,
+    file_system: String,
+}
 
 #[cfg(target_os = "linux")]
 impl Volume {
-    pub fn volume_for_path(_: &PathBuf) -> anyhow::Result<Self> {
-        Err(anyhow::anyhow!("Not implemented."))
+    pub fn device(&self) -> Option<&str> {
+        Some(&self.device)
+    }
+
+    /// Finds the mount (in `/proc/self/mounts`) with the longest mount point containing the path.
+    pub fn volume_for_path(path: &Path) -> anyhow::Result<Self> {
+        let path = path.canonicalize()?;
+        let mounts = std::fs::read_to_string("/proc/self/mounts")?;
+        mounts
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                // Spaces in mount points are escaped as octal.
+                let unescape = |s: &str| s.replace("\\040", " ");
+                (fields.len() >= 3).then(|| Volume {
+                    device: unescape(fields[0]),
+                    mount_point: PathBuf::from(unescape(fields[1])),
+                    file_system: fields[2].to_string(),
+                })
+            })
+            .filter(|volume| path.starts_with(&volume.mount_point))
+            .max_by_key(|volume| volume.mount_point.as_os_str().len())
+            .ok_or(anyhow::anyhow!(
+                "Failed to find volume for path {}",
+                path.display()
+            ))
     }
 }
 
@@ -114,6 +146,10 @@ pub struct Volume {}
 
 #[cfg(target_os = "windows")]
 impl Volume {
+    pub fn device(&self) -> Option<&str> {
+        None
+    }
+
     pub fn volume_for_path(_: &PathBuf) -> anyhow::Result<Self> {
         return Err(anyhow::anyhow!("Not implemented."));
     }